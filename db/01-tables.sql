@@ -5,7 +5,9 @@ CREATE TABLE IF NOT EXISTS users
     id bigint NOT NULL,
     username character varying(20) COLLATE pg_catalog."default" NOT NULL,
     password_hash text COLLATE pg_catalog."default" NOT NULL,
-    flags bit(2) NOT NULL,
+    flags bit(8) NOT NULL,
+    deleted_at timestamp with time zone,
+    suspended_until timestamp with time zone,
     CONSTRAINT users_pkey PRIMARY KEY (id),
     CONSTRAINT users_username_key UNIQUE (username)
 );
@@ -20,7 +22,7 @@ CREATE TABLE IF NOT EXISTS emails
     email text COLLATE pg_catalog."default" NOT NULL,
     is_verified boolean NOT NULL DEFAULT false,
     CONSTRAINT emails_pkey PRIMARY KEY (id),
-    CONSTRAINT emails_email_key UNIQUE (email),
+    CONSTRAINT emails_user_id_email_key UNIQUE (user_id, email),
     CONSTRAINT emails_user_id_fkey FOREIGN KEY (user_id)
         REFERENCES users (id) MATCH SIMPLE
         ON UPDATE NO ACTION
@@ -76,6 +78,7 @@ CREATE TABLE IF NOT EXISTS posts
     content text COLLATE pg_catalog."default",
     is_edited boolean NOT NULL DEFAULT false,
     attachments bigint[],
+    is_hidden boolean NOT NULL DEFAULT false,
     CONSTRAINT posts_pkey PRIMARY KEY (id),
     CONSTRAINT posts_replies_thread_id_fkey FOREIGN KEY (replies_thread_id)
         REFERENCES threads (id) MATCH SIMPLE
@@ -90,3 +93,73 @@ CREATE TABLE IF NOT EXISTS posts
         ON UPDATE NO ACTION
         ON DELETE NO ACTION
 );
+
+-- 6 "attachments" table
+-- depends on: "users"
+-- ------------------------------------------------
+CREATE TABLE IF NOT EXISTS attachments
+(
+    id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    mime_type character varying(127) COLLATE pg_catalog."default" NOT NULL,
+    size bigint NOT NULL,
+    checksum character(64) COLLATE pg_catalog."default" NOT NULL,
+    CONSTRAINT attachments_pkey PRIMARY KEY (id),
+    CONSTRAINT attachments_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+-- 7 "username_history" table
+-- depends on: "users"
+-- ------------------------------------------------
+CREATE TABLE IF NOT EXISTS username_history
+(
+    id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    username character varying(20) COLLATE pg_catalog."default" NOT NULL,
+    changed_at timestamp with time zone NOT NULL DEFAULT now(),
+    CONSTRAINT username_history_pkey PRIMARY KEY (id),
+    CONSTRAINT username_history_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+-- 8 "audit_log" table
+-- depends on: "users"
+-- ------------------------------------------------
+CREATE TABLE IF NOT EXISTS audit_log
+(
+    id bigint NOT NULL,
+    actor_id bigint NOT NULL,
+    action character varying(32) COLLATE pg_catalog."default" NOT NULL,
+    target_id bigint NOT NULL,
+    details text COLLATE pg_catalog."default",
+    CONSTRAINT audit_log_pkey PRIMARY KEY (id),
+    CONSTRAINT audit_log_actor_id_fkey FOREIGN KEY (actor_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+-- 9 "reports" table
+-- depends on: "users"
+-- ------------------------------------------------
+CREATE TABLE IF NOT EXISTS reports
+(
+    id bigint NOT NULL,
+    reporter_id bigint NOT NULL,
+    target_type character varying(8) COLLATE pg_catalog."default" NOT NULL,
+    target_id bigint NOT NULL,
+    reason character varying(16) COLLATE pg_catalog."default" NOT NULL,
+    details text COLLATE pg_catalog."default",
+    CONSTRAINT reports_pkey PRIMARY KEY (id),
+    CONSTRAINT reports_reporter_id_target_type_target_id_key
+        UNIQUE (reporter_id, target_type, target_id),
+    CONSTRAINT reports_reporter_id_fkey FOREIGN KEY (reporter_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
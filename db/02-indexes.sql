@@ -19,3 +19,46 @@ CREATE INDEX IF NOT EXISTS posts_replies_thread_id_id_idx
     INCLUDE(id)
     WITH (fillfactor=100, deduplicate_items=True);
 
+-- Index to speed up lookups of posts referencing an attachment
+CREATE INDEX IF NOT EXISTS posts_attachments_idx
+    ON posts USING gin
+    (attachments);
+
+-- Index to speed up post lookups by user_id
+CREATE INDEX IF NOT EXISTS posts_user_id_id_idx
+    ON posts USING btree
+    (user_id ASC NULLS LAST)
+    INCLUDE(id)
+    WITH (fillfactor=100, deduplicate_items=True);
+
+-- Index to speed up reservation lookups of released usernames
+CREATE INDEX IF NOT EXISTS username_history_username_changed_at_idx
+    ON username_history USING btree
+    (username COLLATE pg_catalog."default" ASC NULLS LAST, changed_at DESC NULLS LAST)
+    INCLUDE(user_id)
+    WITH (fillfactor=100, deduplicate_items=True);
+
+-- Index to speed up lookups of soft-deleted users awaiting purge
+CREATE INDEX IF NOT EXISTS users_deleted_at_idx
+    ON users USING btree
+    (deleted_at ASC NULLS LAST)
+    WHERE deleted_at IS NOT NULL;
+
+-- Index to speed up grouping reports by their target
+CREATE INDEX IF NOT EXISTS reports_target_type_target_id_idx
+    ON reports USING btree
+    (target_type COLLATE pg_catalog."default" ASC NULLS LAST, target_id ASC NULLS LAST)
+    INCLUDE(reason)
+    WITH (fillfactor=100, deduplicate_items=True);
+
+-- Index ensuring an email can be verified by a single account
+CREATE UNIQUE INDEX IF NOT EXISTS emails_email_verified_idx
+    ON emails USING btree
+    (email COLLATE pg_catalog."default" ASC NULLS LAST)
+    WHERE is_verified;
+
+-- Index to speed up username history lookups by user_id
+CREATE INDEX IF NOT EXISTS username_history_user_id_id_idx
+    ON username_history USING btree
+    (user_id ASC NULLS LAST, id DESC NULLS LAST)
+    WITH (fillfactor=100, deduplicate_items=True);
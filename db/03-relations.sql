@@ -9,6 +9,7 @@ CREATE TABLE IF NOT EXISTS relations.likes
     user_id bigint NOT NULL,
     post_id bigint NOT NULL,
     CONSTRAINT likes_id_pkey PRIMARY KEY (id),
+    CONSTRAINT likes_user_id_post_id_key UNIQUE (user_id, post_id),
     CONSTRAINT likes_post_id_fkey FOREIGN KEY (post_id)
         REFERENCES posts (id) MATCH SIMPLE
         ON UPDATE NO ACTION
@@ -40,6 +41,7 @@ CREATE TABLE IF NOT EXISTS relations.follows
     follower_id bigint NOT NULL,
     user_id bigint NOT NULL,
     CONSTRAINT follows_id_pkey PRIMARY KEY (id),
+    CONSTRAINT follows_follower_id_user_id_key UNIQUE (follower_id, user_id),
     CONSTRAINT follows_follower_id_fkey FOREIGN KEY (follower_id)
         REFERENCES users (id) MATCH SIMPLE
         ON UPDATE NO ACTION
@@ -61,3 +63,49 @@ CREATE INDEX IF NOT EXISTS follows_user_id_follower_id_idx
     (user_id ASC NULLS LAST)
     INCLUDE(follower_id)
     WITH (fillfactor=100, deduplicate_items=True);
+
+-- 3 "blocks" table
+-- blocker_id blocks -> user_id
+-- ------------------------------------------------
+CREATE TABLE IF NOT EXISTS relations.blocks
+(
+    id bigint NOT NULL,
+    blocker_id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    CONSTRAINT blocks_id_pkey PRIMARY KEY (id),
+    CONSTRAINT blocks_blocker_id_user_id_key UNIQUE (blocker_id, user_id),
+    CONSTRAINT blocks_blocker_id_fkey FOREIGN KEY (blocker_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT blocks_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE INDEX IF NOT EXISTS blocks_user_id_blocker_id_idx
+    ON relations.blocks USING btree
+    (user_id ASC NULLS LAST)
+    INCLUDE(blocker_id)
+    WITH (fillfactor=100, deduplicate_items=True);
+
+-- 4 "mutes" table
+-- muter_id mutes -> user_id
+-- ------------------------------------------------
+CREATE TABLE IF NOT EXISTS relations.mutes
+(
+    id bigint NOT NULL,
+    muter_id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    CONSTRAINT mutes_id_pkey PRIMARY KEY (id),
+    CONSTRAINT mutes_muter_id_user_id_key UNIQUE (muter_id, user_id),
+    CONSTRAINT mutes_muter_id_fkey FOREIGN KEY (muter_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT mutes_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
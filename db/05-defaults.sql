@@ -0,0 +1,5 @@
+-- Placeholder owner of content that outlives deleted accounts
+-- ------------------------------------------------
+INSERT INTO users (id, username, password_hash, flags)
+VALUES (0, 'deleted', '', b'00000000')
+ON CONFLICT DO NOTHING;
@@ -4,46 +4,46 @@
 -- ------------------------------------------------
 INSERT INTO users (id, username, password_hash, flags)
 VALUES
-    (1001, 'user01', 'argon2passwd', b'00'),
-    (1002, 'user02', 'argon2passwd', b'00'),
-    (1003, 'user03', 'argon2passwd', b'00'),
-    (1004, 'user04', 'argon2passwd', b'00'),
-    (1005, 'user05', 'argon2passwd', b'00'),
-    (1006, 'user06', 'argon2passwd', b'00'),
-    (1007, 'user07', 'argon2passwd', b'00'),
-    (1008, 'user08', 'argon2passwd', b'00'),
-    (1009, 'user09', 'argon2passwd', b'00'),
-    (1010, 'user10', 'argon2passwd', b'00'),
-    (1011, 'user11', 'argon2passwd', b'00'),
-    (1012, 'user12', 'argon2passwd', b'00'),
-    (1013, 'user13', 'argon2passwd', b'00'),
-    (1014, 'user14', 'argon2passwd', b'00'),
-    (1015, 'user15', 'argon2passwd', b'00'),
-    (1016, 'user16', 'argon2passwd', b'00'),
-    (1017, 'user17', 'argon2passwd', b'00'),
-    (1018, 'user18', 'argon2passwd', b'00'),
-    (1019, 'user19', 'argon2passwd', b'00'),
-    (1020, 'user20', 'argon2passwd', b'00'),
-    (1021, 'user21', 'argon2passwd', b'00'),
-    (1022, 'user22', 'argon2passwd', b'00'),
-    (1023, 'user23', 'argon2passwd', b'00'),
-    (1024, 'user24', 'argon2passwd', b'00'),
-    (1025, 'user25', 'argon2passwd', b'00'),
-    (1026, 'user26', 'argon2passwd', b'00'),
-    (1027, 'user27', 'argon2passwd', b'00'),
-    (1028, 'user28', 'argon2passwd', b'00'),
-    (1029, 'user29', 'argon2passwd', b'00'),
-    (1030, 'user30', 'argon2passwd', b'00'),
-    (1031, 'user31', 'argon2passwd', b'00'),
-    (1032, 'user32', 'argon2passwd', b'00'),
-    (1033, 'user33', 'argon2passwd', b'00'),
-    (1034, 'user34', 'argon2passwd', b'00'),
-    (1035, 'user35', 'argon2passwd', b'00'),
-    (1036, 'user36', 'argon2passwd', b'00'),
-    (1037, 'user37', 'argon2passwd', b'00'),
-    (1038, 'user38', 'argon2passwd', b'00'),
-    (1039, 'user39', 'argon2passwd', b'00'),
-    (1040, 'user40', 'argon2passwd', b'00');
+    (1001, 'user01', 'argon2passwd', b'00000000'),
+    (1002, 'user02', 'argon2passwd', b'00000000'),
+    (1003, 'user03', 'argon2passwd', b'00000000'),
+    (1004, 'user04', 'argon2passwd', b'00000000'),
+    (1005, 'user05', 'argon2passwd', b'00000000'),
+    (1006, 'user06', 'argon2passwd', b'00000000'),
+    (1007, 'user07', 'argon2passwd', b'00000000'),
+    (1008, 'user08', 'argon2passwd', b'00000000'),
+    (1009, 'user09', 'argon2passwd', b'00000000'),
+    (1010, 'user10', 'argon2passwd', b'00000000'),
+    (1011, 'user11', 'argon2passwd', b'00000000'),
+    (1012, 'user12', 'argon2passwd', b'00000000'),
+    (1013, 'user13', 'argon2passwd', b'00000000'),
+    (1014, 'user14', 'argon2passwd', b'00000000'),
+    (1015, 'user15', 'argon2passwd', b'00000000'),
+    (1016, 'user16', 'argon2passwd', b'00000000'),
+    (1017, 'user17', 'argon2passwd', b'00000000'),
+    (1018, 'user18', 'argon2passwd', b'00000000'),
+    (1019, 'user19', 'argon2passwd', b'00000000'),
+    (1020, 'user20', 'argon2passwd', b'00000000'),
+    (1021, 'user21', 'argon2passwd', b'00000000'),
+    (1022, 'user22', 'argon2passwd', b'00000000'),
+    (1023, 'user23', 'argon2passwd', b'00000000'),
+    (1024, 'user24', 'argon2passwd', b'00000000'),
+    (1025, 'user25', 'argon2passwd', b'00000000'),
+    (1026, 'user26', 'argon2passwd', b'00000000'),
+    (1027, 'user27', 'argon2passwd', b'00000000'),
+    (1028, 'user28', 'argon2passwd', b'00000000'),
+    (1029, 'user29', 'argon2passwd', b'00000000'),
+    (1030, 'user30', 'argon2passwd', b'00000000'),
+    (1031, 'user31', 'argon2passwd', b'00000000'),
+    (1032, 'user32', 'argon2passwd', b'00000000'),
+    (1033, 'user33', 'argon2passwd', b'00000000'),
+    (1034, 'user34', 'argon2passwd', b'00000000'),
+    (1035, 'user35', 'argon2passwd', b'00000000'),
+    (1036, 'user36', 'argon2passwd', b'00000000'),
+    (1037, 'user37', 'argon2passwd', b'00000000'),
+    (1038, 'user38', 'argon2passwd', b'00000000'),
+    (1039, 'user39', 'argon2passwd', b'00000000'),
+    (1040, 'user40', 'argon2passwd', b'00000000');
 
 -- 2 "threads" mockdata
 --
@@ -0,0 +1,15 @@
+-- Makes likes unique per user and post
+--
+-- Duplicate likes are removed first, keeping the earliest one.
+-- ------------------------------------------------
+DELETE FROM relations.likes
+WHERE id IN (
+    SELECT id FROM (
+        SELECT id, row_number() OVER (PARTITION BY user_id, post_id ORDER BY id) AS n
+        FROM relations.likes
+    ) AS likes
+    WHERE n > 1
+);
+
+ALTER TABLE relations.likes
+    ADD CONSTRAINT likes_user_id_post_id_key UNIQUE (user_id, post_id);
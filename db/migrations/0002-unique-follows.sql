@@ -0,0 +1,15 @@
+-- Makes follows unique per follower and user
+--
+-- Duplicate follows are removed first, keeping the earliest one.
+-- ------------------------------------------------
+DELETE FROM relations.follows
+WHERE id IN (
+    SELECT id FROM (
+        SELECT id, row_number() OVER (PARTITION BY follower_id, user_id ORDER BY id) AS n
+        FROM relations.follows
+    ) AS follows
+    WHERE n > 1
+);
+
+ALTER TABLE relations.follows
+    ADD CONSTRAINT follows_follower_id_user_id_key UNIQUE (follower_id, user_id);
@@ -0,0 +1,7 @@
+-- Indexes posts by their author for timelines
+-- ------------------------------------------------
+CREATE INDEX IF NOT EXISTS posts_user_id_id_idx
+    ON posts USING btree
+    (user_id ASC NULLS LAST)
+    INCLUDE(id)
+    WITH (fillfactor=100, deduplicate_items=True);
@@ -0,0 +1,12 @@
+-- Adds attachments that posts and profiles can reference
+-- ------------------------------------------------
+CREATE TABLE IF NOT EXISTS attachments
+(
+    id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    CONSTRAINT attachments_pkey PRIMARY KEY (id),
+    CONSTRAINT attachments_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
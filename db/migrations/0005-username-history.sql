@@ -0,0 +1,20 @@
+-- Adds username history for reserving released usernames
+-- ------------------------------------------------
+CREATE TABLE IF NOT EXISTS username_history
+(
+    id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    username character varying(20) COLLATE pg_catalog."default" NOT NULL,
+    changed_at timestamp with time zone NOT NULL DEFAULT now(),
+    CONSTRAINT username_history_pkey PRIMARY KEY (id),
+    CONSTRAINT username_history_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE INDEX IF NOT EXISTS username_history_username_changed_at_idx
+    ON username_history USING btree
+    (username COLLATE pg_catalog."default" ASC NULLS LAST, changed_at DESC NULLS LAST)
+    INCLUDE(user_id)
+    WITH (fillfactor=100, deduplicate_items=True);
@@ -0,0 +1,16 @@
+-- Adds soft deletion of accounts and the placeholder owner of content that
+-- outlives deleted accounts
+--
+-- Fails if an user named "deleted" already exists, rename it first.
+-- ------------------------------------------------
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS deleted_at timestamp with time zone;
+
+CREATE INDEX IF NOT EXISTS users_deleted_at_idx
+    ON users USING btree
+    (deleted_at ASC NULLS LAST)
+    WHERE deleted_at IS NOT NULL;
+
+INSERT INTO users (id, username, password_hash, flags)
+VALUES (0, 'deleted', '', b'00')
+ON CONFLICT (id) DO NOTHING;
@@ -0,0 +1,6 @@
+-- Widens "users"."flags" from 2 to 8 bits for typed user flags
+--
+-- Existing bits keep their positions, new bits are zero padded.
+-- ------------------------------------------------
+ALTER TABLE users
+    ALTER COLUMN flags TYPE bit(8) USING flags::bit(8);
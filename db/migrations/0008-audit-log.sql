@@ -0,0 +1,15 @@
+-- Adds the audit log of moderation and administration actions
+-- ------------------------------------------------
+CREATE TABLE IF NOT EXISTS audit_log
+(
+    id bigint NOT NULL,
+    actor_id bigint NOT NULL,
+    action character varying(32) COLLATE pg_catalog."default" NOT NULL,
+    target_id bigint NOT NULL,
+    details text COLLATE pg_catalog."default",
+    CONSTRAINT audit_log_pkey PRIMARY KEY (id),
+    CONSTRAINT audit_log_actor_id_fkey FOREIGN KEY (actor_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
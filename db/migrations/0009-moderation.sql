@@ -0,0 +1,7 @@
+-- Adds hidden posts and user suspensions for moderation
+-- ------------------------------------------------
+ALTER TABLE posts
+    ADD COLUMN IF NOT EXISTS is_hidden boolean NOT NULL DEFAULT false;
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS suspended_until timestamp with time zone;
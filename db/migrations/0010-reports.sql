@@ -0,0 +1,24 @@
+-- Adds user reports of posts and users
+-- ------------------------------------------------
+CREATE TABLE IF NOT EXISTS reports
+(
+    id bigint NOT NULL,
+    reporter_id bigint NOT NULL,
+    target_type character varying(8) COLLATE pg_catalog."default" NOT NULL,
+    target_id bigint NOT NULL,
+    reason character varying(16) COLLATE pg_catalog."default" NOT NULL,
+    details text COLLATE pg_catalog."default",
+    CONSTRAINT reports_pkey PRIMARY KEY (id),
+    CONSTRAINT reports_reporter_id_target_type_target_id_key
+        UNIQUE (reporter_id, target_type, target_id),
+    CONSTRAINT reports_reporter_id_fkey FOREIGN KEY (reporter_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE INDEX IF NOT EXISTS reports_target_type_target_id_idx
+    ON reports USING btree
+    (target_type COLLATE pg_catalog."default" ASC NULLS LAST, target_id ASC NULLS LAST)
+    INCLUDE(reason)
+    WITH (fillfactor=100, deduplicate_items=True);
@@ -0,0 +1,41 @@
+-- Adds user blocks and mutes
+-- ------------------------------------------------
+CREATE TABLE IF NOT EXISTS relations.blocks
+(
+    id bigint NOT NULL,
+    blocker_id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    CONSTRAINT blocks_id_pkey PRIMARY KEY (id),
+    CONSTRAINT blocks_blocker_id_user_id_key UNIQUE (blocker_id, user_id),
+    CONSTRAINT blocks_blocker_id_fkey FOREIGN KEY (blocker_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT blocks_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
+
+CREATE INDEX IF NOT EXISTS blocks_user_id_blocker_id_idx
+    ON relations.blocks USING btree
+    (user_id ASC NULLS LAST)
+    INCLUDE(blocker_id)
+    WITH (fillfactor=100, deduplicate_items=True);
+
+CREATE TABLE IF NOT EXISTS relations.mutes
+(
+    id bigint NOT NULL,
+    muter_id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    CONSTRAINT mutes_id_pkey PRIMARY KEY (id),
+    CONSTRAINT mutes_muter_id_user_id_key UNIQUE (muter_id, user_id),
+    CONSTRAINT mutes_muter_id_fkey FOREIGN KEY (muter_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION,
+    CONSTRAINT mutes_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users (id) MATCH SIMPLE
+        ON UPDATE NO ACTION
+        ON DELETE NO ACTION
+);
@@ -0,0 +1,16 @@
+-- Adds MIME type, size and checksum of uploaded attachments
+--
+-- Attachments created before the upload endpoint have no stored file, they
+-- are marked as empty octet streams.
+-- ------------------------------------------------
+ALTER TABLE attachments
+    ADD COLUMN IF NOT EXISTS mime_type character varying(127) NOT NULL
+        DEFAULT 'application/octet-stream',
+    ADD COLUMN IF NOT EXISTS size bigint NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS checksum character(64) NOT NULL
+        DEFAULT 'e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855';
+
+ALTER TABLE attachments
+    ALTER COLUMN mime_type DROP DEFAULT,
+    ALTER COLUMN size DROP DEFAULT,
+    ALTER COLUMN checksum DROP DEFAULT;
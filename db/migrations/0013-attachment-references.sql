@@ -0,0 +1,6 @@
+-- Indexes attachment references of posts for the orphaned attachment
+-- collector
+-- ------------------------------------------------
+CREATE INDEX IF NOT EXISTS posts_attachments_idx
+    ON posts USING gin
+    (attachments);
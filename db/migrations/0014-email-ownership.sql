@@ -0,0 +1,11 @@
+-- Lets several accounts claim an unverified email, while only one account can
+-- verify it
+-- ------------------------------------------------
+ALTER TABLE emails
+    DROP CONSTRAINT IF EXISTS emails_email_key,
+    ADD CONSTRAINT emails_user_id_email_key UNIQUE (user_id, email);
+
+CREATE UNIQUE INDEX IF NOT EXISTS emails_email_verified_idx
+    ON emails USING btree
+    (email COLLATE pg_catalog."default" ASC NULLS LAST)
+    WHERE is_verified;
@@ -0,0 +1,7 @@
+-- Adds username history index used for finding the most recently released
+-- username of a user
+-- ------------------------------------------------
+CREATE INDEX IF NOT EXISTS username_history_user_id_id_idx
+    ON username_history USING btree
+    (user_id ASC NULLS LAST, id DESC NULLS LAST)
+    WITH (fillfactor=100, deduplicate_items=True);
@@ -0,0 +1,315 @@
+use crate::{
+    AppState, dto::auth::AuthError, entity, response::AppError, service::token_service::AuthToken,
+};
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, OptionalFromRequestParts},
+    http::{HeaderMap, header::AUTHORIZATION, request::Parts},
+};
+use std::{
+    convert::Infallible,
+    marker::PhantomData,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
+
+/// Authenticated caller.
+///
+/// Reads the JWT from `Authorization: Bearer <token>` header and validates it
+/// with [`AppState::auth_token_service`]. Use `Option<Auth>` for endpoints
+/// that also serve anonymous callers; requests without `Authorization` header
+/// are extracted as [`None`], while invalid tokens are still rejected.
+/// Suspended users are rejected in both cases.
+///
+/// Validated tokens are kept in request extensions, so extracting it again
+/// later in the same request (e.g. after a middleware) does not hit Redis.
+pub struct Auth(pub AuthToken);
+
+/// Token validated earlier in the request
+#[derive(Clone)]
+pub(crate) struct ValidatedToken(AuthToken);
+
+/// Returns bearer token from `Authorization` header, if header present.
+fn bearer_token(parts: &Parts) -> Option<Result<&str, AuthError>> {
+    let header = parts.headers.get(AUTHORIZATION)?;
+
+    Some(
+        header
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .ok_or(AuthError::InvalidToken),
+    )
+}
+
+/// Validates bearer token of the request, if header present.
+async fn authenticate(parts: &mut Parts, state: &AppState) -> Option<Result<Auth, AppError>> {
+    if let Some(ValidatedToken(token)) = parts.extensions.get() {
+        return Some(Ok(Auth(token.clone())));
+    }
+
+    let result = match bearer_token(parts)? {
+        Ok(jwt) => validate(state, jwt).await,
+        Err(err) => Err(err.into()),
+    };
+
+    if let Ok(Auth(token)) = &result {
+        parts.extensions.insert(ValidatedToken(token.clone()));
+    }
+
+    Some(result)
+}
+
+async fn validate(state: &AppState, jwt: &str) -> Result<Auth, AppError> {
+    let Some(token) = state.auth_token_service.validate(jwt).await else {
+        return Err(AuthError::InvalidToken.into());
+    };
+
+    match state
+        .moderation_service
+        .get_cached_suspension(token.id)
+        .await
+    {
+        Some(remaining) => Err(AuthError::AccountSuspended(remaining).into()),
+        None => Ok(Auth(token)),
+    }
+}
+
+impl FromRequestParts<AppState> for Auth {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, AppError> {
+        authenticate(parts, state)
+            .await
+            .unwrap_or(Err(AuthError::MissingToken.into()))
+    }
+}
+
+impl OptionalFromRequestParts<AppState> for Auth {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Option<Self>, AppError> {
+        authenticate(parts, state).await.transpose()
+    }
+}
+
+/// Permission required by [`Authorized`].
+pub trait Permission {
+    /// Whether or not the user has the permission.
+    fn is_granted(user: &entity::User) -> bool;
+}
+
+/// Permission of admins
+pub struct Admin;
+
+impl Permission for Admin {
+    fn is_granted(user: &entity::User) -> bool {
+        user.is_admin()
+    }
+}
+
+/// Permission of moderators, admins have it as well
+pub struct Moderator;
+
+impl Permission for Moderator {
+    fn is_granted(user: &entity::User) -> bool {
+        user.is_moderator()
+    }
+}
+
+/// Authenticated caller that has the permission `P`.
+///
+/// Unlike [`Auth`], flags of the caller are read from the database on each
+/// request so revoked permissions take effect immediately. Banned users are
+/// always rejected.
+pub struct Authorized<P: Permission>(pub entity::User, pub PhantomData<P>);
+
+impl<P: Permission> FromRequestParts<AppState> for Authorized<P> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, AppError> {
+        let Auth(token) = <Auth as FromRequestParts<_>>::from_request_parts(parts, state).await?;
+
+        let Some(user) = state.user_service.get_user_entity_by_id(token.id).await else {
+            return Err(AuthError::InvalidToken.into());
+        };
+
+        if user.is_banned() {
+            Err(AuthError::AccountBanned.into())
+        } else if P::is_granted(&user) {
+            Ok(Authorized(user, PhantomData))
+        } else {
+            Err(AuthError::MissingPermission.into())
+        }
+    }
+}
+
+/// IP address of the client.
+///
+/// Read from `X-Forwarded-For` header if [`Config::trusted_proxies`] is set,
+/// otherwise from the peer address of the connection. Falls back to
+/// unspecified address if neither is available.
+///
+/// [`Config::trusted_proxies`]: crate::Config::trusted_proxies
+pub struct ClientIp(pub IpAddr);
+
+/// Returns the client IP recorded by the outermost of `hops` trusted proxies.
+///
+/// Each proxy appends the address it received the request from, so the
+/// entries left of it are supplied by the client and cannot be trusted.
+fn forwarded_ip(headers: &HeaderMap, hops: usize) -> Option<IpAddr> {
+    if hops == 0 {
+        return None;
+    }
+
+    let entries: Vec<_> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+
+    entries
+        .len()
+        .checked_sub(hops)
+        .and_then(|index| entries[index].trim().parse().ok())
+}
+
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Infallible> {
+        let ip = forwarded_ip(&parts.headers, state.config.trusted_proxies)
+            .or_else(|| {
+                parts
+                    .extensions
+                    .get::<ConnectInfo<SocketAddr>>()
+                    .map(|ConnectInfo(address)| address.ip())
+            })
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+        Ok(ClientIp(ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::test_state;
+    use axum::http::Request;
+    use serial_test::serial;
+
+    fn parts(authorization: Option<&str>) -> Parts {
+        let mut request = Request::builder();
+
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+
+        request.body(()).unwrap().into_parts().0
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn authorization() {
+        let state = test_state().await;
+        let jwt = state
+            .auth_token_service
+            .sign(AuthToken::new(1002, String::from("user02")))
+            .await;
+        let authorization = format!("Bearer {jwt}");
+
+        assert!(
+            Authorized::<Moderator>::from_request_parts(&mut parts(Some(&authorization)), &state)
+                .await
+                .is_err()
+        );
+
+        for (flag, value) in [
+            (entity::UserFlag::Moderator, true),
+            (entity::UserFlag::Moderator, false),
+        ] {
+            state
+                .user_service
+                .set_user_flag(1001, 1002, flag, value)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                Authorized::<Moderator>::from_request_parts(
+                    &mut parts(Some(&authorization)),
+                    &state
+                )
+                .await
+                .is_ok(),
+                value
+            );
+            assert!(
+                Authorized::<Admin>::from_request_parts(&mut parts(Some(&authorization)), &state)
+                    .await
+                    .is_err()
+            );
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn extraction() {
+        let state = test_state().await;
+        let jwt = state
+            .auth_token_service
+            .sign(AuthToken::new(1001, String::from("user01")))
+            .await;
+
+        let Auth(token) = <Auth as FromRequestParts<_>>::from_request_parts(
+            &mut parts(Some(&format!("Bearer {jwt}"))),
+            &state,
+        )
+        .await
+        .unwrap();
+        assert_eq!(token.id, 1001);
+
+        for authorization in [None, Some("Bearer"), Some(jwt.as_str()), Some("Bearer x")] {
+            assert!(
+                <Auth as FromRequestParts<_>>::from_request_parts(
+                    &mut parts(authorization),
+                    &state
+                )
+                .await
+                .is_err()
+            );
+        }
+
+        assert!(
+            <Auth as OptionalFromRequestParts<_>>::from_request_parts(&mut parts(None), &state)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            <Auth as OptionalFromRequestParts<_>>::from_request_parts(
+                &mut parts(Some("Bearer x")),
+                &state
+            )
+            .await
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn forwarded_ip() {
+        // "10.0.0.1" is spoofed by the client, the rest is appended by proxies
+        let mut headers = HeaderMap::new();
+        headers.append("x-forwarded-for", "10.0.0.1, 192.0.2.1".parse().unwrap());
+        headers.append("x-forwarded-for", "198.51.100.1".parse().unwrap());
+
+        let ip = |hops| super::forwarded_ip(&headers, hops);
+        assert_eq!(ip(0), None);
+        assert_eq!(ip(1), Some("198.51.100.1".parse().unwrap()));
+        assert_eq!(ip(2), Some("192.0.2.1".parse().unwrap()));
+        assert_eq!(ip(4), None);
+    }
+}
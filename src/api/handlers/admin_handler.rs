@@ -0,0 +1,85 @@
+use crate::{
+    AppState,
+    dto::{
+        auth,
+        user::{UserError, error_examples},
+    },
+    entity::UserFlag,
+    extract::{Admin, Authorized},
+    response::AppError,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+
+async fn set_user_flag(
+    state: &AppState,
+    actor_id: i64,
+    user_id: i64,
+    flag: UserFlag,
+    value: bool,
+) -> Result<StatusCode, AppError> {
+    if state.user_service.get_user_by_id(user_id).await.is_none() {
+        return Err(UserError::UserNotFound.into());
+    }
+
+    let changed = state
+        .user_service
+        .set_user_flag(actor_id, user_id, flag, value)
+        .await
+        .ok_or(AppError::InternalServerError)?;
+
+    if changed && flag == UserFlag::Banned && value {
+        state.auth_token_service.revoke_tokens_for(user_id).await;
+        state.refresh_token_service.revoke_tokens_for(user_id).await;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Grants a flag to an user.
+///
+/// Sets one of `admin`, `moderator`, `verified` or `banned` flags of the
+/// user and records it in the audit log. Banning an user revokes all of its
+/// tokens.
+#[utoipa::path(
+    put,
+    path = "/users/{id}/flags/{flag}",
+    responses(
+        (status = NO_CONTENT, description = "Flag granted"),
+        error_examples::UserNotFoundDto,
+        auth::error_examples::ForbiddenDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn grant_flag(
+    State(state): State<AppState>,
+    Authorized(admin, _): Authorized<Admin>,
+    Path((id, flag)): Path<(i64, UserFlag)>,
+) -> Result<StatusCode, AppError> {
+    set_user_flag(&state, admin.id, id, flag, true).await
+}
+
+/// Revokes a flag from an user.
+///
+/// Clears the flag of the user and records it in the audit log.
+#[utoipa::path(
+    delete,
+    path = "/users/{id}/flags/{flag}",
+    responses(
+        (status = NO_CONTENT, description = "Flag revoked"),
+        error_examples::UserNotFoundDto,
+        auth::error_examples::ForbiddenDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn revoke_flag(
+    State(state): State<AppState>,
+    Authorized(admin, _): Authorized<Admin>,
+    Path((id, flag)): Path<(i64, UserFlag)>,
+) -> Result<StatusCode, AppError> {
+    set_user_flag(&state, admin.id, id, flag, false).await
+}
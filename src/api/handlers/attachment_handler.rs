@@ -0,0 +1,214 @@
+use crate::{
+    AppState,
+    dto::{
+        attachments::{
+            AttachmentDto, AttachmentError, AttachmentQuery, UploadAttachmentDto, error_examples,
+        },
+        auth,
+    },
+    extract::Auth,
+    media::{self, MediaError},
+    response::{AppError, AppOk, AppResult},
+    util::detect_mime_type,
+};
+use axum::{
+    extract::{Multipart, Path, Query, State, multipart::MultipartError},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+
+/// Maps errors while reading the multipart body, body limit rejections are
+/// reported with the configured size limit.
+fn multipart_error(state: &AppState, err: MultipartError) -> AppError {
+    if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        AttachmentError::FileTooLarge(state.config.attachment_max_size).into()
+    } else {
+        AttachmentError::UnreadableFile.into()
+    }
+}
+
+/// Uploads a file.
+///
+/// Accepts a `multipart/form-data` body with a `file` field. File type is
+/// detected from its content and must be one of the types allowed by the
+/// server. The returned id can be used as post attachments, avatar or banner.
+///
+/// Images are re-encoded without their metadata, and their `avatar`,
+/// `banner` and `thumb` variants are generated. Animated GIFs can have at
+/// most 256 frames. WebP images are stored as JPEG, or as PNG if they have
+/// transparency, and animated WebP images are not accepted. The re-encoded
+/// image is subject to the size limit as well.
+#[utoipa::path(
+    post,
+    path = "",
+    request_body(content = UploadAttachmentDto, content_type = "multipart/form-data"),
+    responses(
+        (status = OK, description = "Uploaded attachment", body = AttachmentDto),
+        error_examples::InappropriateAttachmentDto,
+        error_examples::AttachmentTooLargeDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn upload_attachment(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    mut multipart: Multipart,
+) -> AppResult<AttachmentDto> {
+    let max_size = state.config.attachment_max_size;
+
+    let mut field = loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.name() == Some("file") => break field,
+            Ok(Some(_)) => continue,
+            Ok(None) => return Err(AttachmentError::MissingFile.into()),
+            Err(err) => return Err(multipart_error(&state, err)),
+        }
+    };
+
+    let mut data = Vec::new();
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|err| multipart_error(&state, err))?
+    {
+        if (data.len() + chunk.len()) as u64 > max_size {
+            return Err(AttachmentError::FileTooLarge(max_size).into());
+        }
+
+        data.extend_from_slice(&chunk);
+    }
+
+    if data.is_empty() {
+        return Err(AttachmentError::EmptyFile.into());
+    }
+
+    let Some(mime_type) = detect_mime_type(&data)
+        .filter(|mime_type| state.config.attachment_types.contains(mime_type))
+    else {
+        return Err(AttachmentError::UnsupportedType.into());
+    };
+
+    let (mime_type, data, variants) = match media::image_format(mime_type) {
+        Some(format) => {
+            let max_pixels = state.config.image_max_pixels;
+
+            match media::process_image(data, format, max_pixels).await {
+                Ok(image) => (image.mime_type, image.data, image.variants),
+                Err(MediaError::TooManyPixels) => {
+                    return Err(AttachmentError::ImageTooLarge(max_pixels).into());
+                }
+                Err(MediaError::TooManyFrames) => {
+                    return Err(
+                        AttachmentError::TooManyFrames(media::MAX_ANIMATION_FRAMES as u64).into(),
+                    );
+                }
+                Err(MediaError::UnsupportedAnimation) => {
+                    return Err(AttachmentError::UnsupportedAnimation.into());
+                }
+                Err(MediaError::Malformed) => return Err(AttachmentError::MalformedImage.into()),
+            }
+        }
+        None => (mime_type, data, Vec::new()),
+    };
+
+    // re-encoded images may be larger than the upload
+    if data.len() as u64 > max_size {
+        return Err(AttachmentError::FileTooLarge(max_size).into());
+    }
+
+    if let Some(attachment) = state
+        .attachment_service
+        .upload(token.id, mime_type, data, variants)
+        .await
+    {
+        AppOk(attachment).into()
+    } else {
+        Err(AppError::InternalServerError)
+    }
+}
+
+/// Gets content of an attachment.
+///
+/// Attachments never change, responses can be cached indefinitely. The
+/// `ETag` is the SHA-256 checksum of the file, suffixed with the variant name
+/// for variants. Variants are only available for image attachments, and are
+/// JPEG or, if the image has transparency, PNG encoded.
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    responses(
+        (status = OK, description = "File content", content_type = "application/octet-stream"),
+        (status = NOT_MODIFIED, description = "Cached file is up to date"),
+        error_examples::AttachmentNotFoundDto,
+    ),
+    params(AttachmentQuery),
+)]
+pub async fn get_attachment(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(AttachmentQuery { variant }): Query<AttachmentQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let Some(attachment) = state.attachment_service.get_attachment(id).await else {
+        return Err(AttachmentError::AttachmentNotFound.into());
+    };
+
+    if variant.is_some() && media::image_format(&attachment.mime_type).is_none() {
+        return Err(AttachmentError::VariantNotFound.into());
+    }
+
+    let etag = match variant {
+        Some(variant) => format!("\"{}-{}\"", attachment.checksum, variant.name()),
+        None => format!("\"{}\"", attachment.checksum),
+    };
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (
+            header::CACHE_CONTROL,
+            String::from("public, max-age=31536000, immutable"),
+        ),
+    ];
+
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag))
+    {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+
+    let Some(data) = state
+        .attachment_service
+        .read(id, variant)
+        .await
+        .ok_or(AppError::InternalServerError)?
+    else {
+        if variant.is_some() {
+            // Images uploaded before variants were introduced
+            return Err(AttachmentError::VariantNotFound.into());
+        }
+
+        tracing::error!(id, "Attachment file is missing from storage");
+        return Err(AttachmentError::AttachmentNotFound.into());
+    };
+
+    let content_type = match variant {
+        Some(_) => detect_mime_type(&data).map(HeaderValue::from_static),
+        None => HeaderValue::from_str(&attachment.mime_type).ok(),
+    };
+
+    let mut response = (cache_headers, data).into_response();
+    let response_headers = response.headers_mut();
+
+    response_headers.insert(
+        header::CONTENT_TYPE,
+        content_type.unwrap_or(HeaderValue::from_static("application/octet-stream")),
+    );
+    response_headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+
+    Ok(response)
+}
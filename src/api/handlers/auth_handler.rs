@@ -1,12 +1,91 @@
 use crate::{
-    AppState,
-    dto::auth::{AuthError, AuthUserDto, TokenDto, error_examples},
-    response::{AppOk, AppResult},
-    service::token_service::AuthToken,
+    AppState, Config,
+    dto::{
+        auth::{
+            AuthError, AuthUserDto, ChangePasswordDto, PasswordResetConfirmDto,
+            PasswordResetRequestDto, RefreshTokenDto, TokenDto, error_examples, validate_username,
+        },
+        user::FullProfileDto,
+    },
+    entity::UserFlag,
+    extract::{Auth, ClientIp},
+    response::{AppError, AppOk, AppResult},
+    service::{
+        RateLimit,
+        token_service::{AuthToken, RefreshToken},
+    },
+    state::RateLimitRule,
 };
-use axum::{Json, extract::State};
+use axum::{Json, extract::State, http::StatusCode};
+use std::net::IpAddr;
 use validator::Validate;
 
+/// Checks password against the password policy in config.
+fn check_password_policy(config: &Config, password: &str) -> Result<(), AuthError> {
+    let length = password.chars().count();
+
+    if length < config.password_min_length || length > config.password_max_length {
+        Err(AuthError::PasswordRejected)
+    } else {
+        Ok(())
+    }
+}
+
+/// Counts an attempt for `id` in `scope`, rejecting it if the rule's limit
+/// is exceeded.
+async fn check_rate_limit(
+    state: &AppState,
+    scope: &str,
+    id: &str,
+    rule: RateLimitRule,
+) -> Result<(), AuthError> {
+    match state.rate_limit_service.hit(scope, id, rule).await {
+        Some(RateLimit {
+            retry_after: Some(seconds),
+            ..
+        }) => Err(AuthError::TooManyAttempts(seconds)),
+        _ => Ok(()),
+    }
+}
+
+/// Counts a mail request from the client IP to the email, rejecting it if
+/// either limit is exceeded.
+pub(super) async fn check_mail_rate_limit(
+    state: &AppState,
+    ip: IpAddr,
+    email: &str,
+) -> Result<(), AuthError> {
+    check_rate_limit(
+        state,
+        "mail-ip",
+        &ip.to_string(),
+        state.config.mail_rate_limit_ip,
+    )
+    .await?;
+    check_rate_limit(
+        state,
+        "mail-email",
+        email,
+        state.config.mail_rate_limit_email,
+    )
+    .await
+}
+
+/// Signs a new token pair for the user, starting a new refresh token family.
+async fn issue_tokens(state: &AppState, user: FullProfileDto) -> TokenDto {
+    TokenDto {
+        token: state
+            .auth_token_service
+            .sign(AuthToken::new(user.id, user.username.clone()))
+            .await,
+        refresh_token: state
+            .refresh_token_service
+            .sign_latest(RefreshToken::new(user.id))
+            .await,
+        user,
+    }
+}
+
 /// Register a new account.
 ///
 /// Registering an user account, initializes an empty profile and thread for
@@ -19,26 +98,44 @@ use validator::Validate;
         (status = CREATED, description = "Account info for registered account", body = TokenDto),
         error_examples::CannotCreateAccountDto,
         error_examples::InappropriatePasswordOrUsernameDto,
+        error_examples::TooManyAttemptsDto,
     ),
 )]
 pub async fn register(
     state: State<AppState>,
+    ClientIp(ip): ClientIp,
     Json(mut credentials): Json<AuthUserDto>,
 ) -> AppResult<TokenDto> {
     if !state.config.allow_account_creation {
         return Err(AuthError::RegistrationRejected.into());
     }
 
-    match credentials.username.len() {
-        3..=20 => (),
-        v if v < 3 => return Err(AuthError::UsernameTooShort.into()),
-        _ => return Err(AuthError::UsernameTooLong.into()),
-    };
+    check_rate_limit(
+        &state,
+        "register-ip",
+        &ip.to_string(),
+        state.config.register_rate_limit_ip,
+    )
+    .await?;
 
-    credentials.username = credentials.username.to_lowercase();
+    credentials.username = validate_username(&credentials.username)?;
 
-    if credentials.validate().is_err() {
-        return Err(AuthError::UsernameRejected.into());
+    check_rate_limit(
+        &state,
+        "register-username",
+        &credentials.username,
+        state.config.register_rate_limit_username,
+    )
+    .await?;
+
+    check_password_policy(&state.config, &credentials.password)?;
+
+    if state
+        .user_service
+        .is_username_reserved(&credentials.username, None, state.config.username_cooldown)
+        .await
+    {
+        return Err(AuthError::UsernameTaken(credentials.username).into());
     }
 
     if let Some(user) = state
@@ -46,15 +143,7 @@ pub async fn register(
         .create_user(credentials.username.clone(), credentials.password)
         .await
     {
-        let dto = TokenDto {
-            token: state
-                .auth_token_service
-                .sign(AuthToken::new(user.id, credentials.username))
-                .await,
-            user,
-        };
-
-        AppOk(dto).into()
+        AppOk(issue_tokens(&state, user).await).into()
     } else {
         Err(AuthError::UsernameTaken(credentials.username).into())
     }
@@ -70,18 +159,37 @@ pub async fn register(
     responses(
         (status = OK, description = "Log into existing account", body = TokenDto),
         error_examples::InvalidCredentialsDto,
+        error_examples::ForbiddenDto,
+        error_examples::TooManyAttemptsDto,
     ),
 )]
 pub async fn login(
     state: State<AppState>,
+    ClientIp(ip): ClientIp,
     Json(mut credentials): Json<AuthUserDto>,
 ) -> AppResult<TokenDto> {
+    check_rate_limit(
+        &state,
+        "login-ip",
+        &ip.to_string(),
+        state.config.login_rate_limit_ip,
+    )
+    .await?;
+
     credentials.username = credentials.username.to_lowercase();
 
     if credentials.validate().is_err() {
         return Err(AuthError::UsernameRejected.into());
     }
 
+    check_rate_limit(
+        &state,
+        "login-username",
+        &credentials.username,
+        state.config.login_rate_limit_username,
+    )
+    .await?;
+
     let is_credentials_valid = state
         .user_service
         .validate_password_of_username(&credentials.username, credentials.password)
@@ -94,17 +202,248 @@ pub async fn login(
             .await;
 
         if let Some(user) = user {
-            let dto = TokenDto {
-                token: state
-                    .auth_token_service
-                    .sign(AuthToken::new(user.id, credentials.username))
-                    .await,
-                user,
-            };
-
-            return AppOk(dto).into();
+            if UserFlag::Banned.is_set(&user.flags) {
+                return Err(AuthError::AccountBanned.into());
+            }
+
+            if let Some(remaining) = state.moderation_service.get_suspension(user.id).await {
+                return Err(AuthError::AccountSuspended(remaining).into());
+            }
+
+            return AppOk(issue_tokens(&state, user).await).into();
         }
     }
 
     Err(AuthError::InvalidCredentials.into())
 }
+
+/// Logs out the current session.
+///
+/// Revokes the token used to authenticate this request. If a refresh token
+/// is provided, every refresh token rotated from the same login is revoked
+/// as well.
+#[utoipa::path(
+    post,
+    path = "/logout",
+    request_body(content = Option<RefreshTokenDto>),
+    responses(
+        (status = NO_CONTENT, description = "Token revoked"),
+        error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    body: Option<Json<RefreshTokenDto>>,
+) -> StatusCode {
+    state.auth_token_service.revoke_token(&token).await;
+
+    if let Some(Json(RefreshTokenDto { refresh_token })) = body
+        && let Some(refresh_token) = state.refresh_token_service.validate(&refresh_token).await
+        && refresh_token.id == token.id
+    {
+        state
+            .refresh_token_service
+            .revoke_family(&refresh_token)
+            .await;
+    }
+
+    StatusCode::NO_CONTENT
+}
+
+/// Logs out from all sessions.
+///
+/// Revokes every token and refresh token issued for the authenticated user
+/// until now.
+#[utoipa::path(
+    post,
+    path = "/logout-all",
+    responses(
+        (status = NO_CONTENT, description = "Tokens revoked"),
+        error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn logout_all(State(state): State<AppState>, Auth(token): Auth) -> StatusCode {
+    state.auth_token_service.revoke_tokens_for(token.id).await;
+    state
+        .refresh_token_service
+        .revoke_tokens_for(token.id)
+        .await;
+
+    StatusCode::NO_CONTENT
+}
+
+/// Changes password of the account.
+///
+/// Revokes all existing tokens of the user and returns a fresh one.
+#[utoipa::path(
+    post,
+    path = "/password",
+    request_body = ChangePasswordDto,
+    responses(
+        (status = OK, description = "Password changed", body = TokenDto),
+        error_examples::InappropriatePasswordOrUsernameDto,
+        error_examples::InvalidCredentialsDto,
+        error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Json(passwords): Json<ChangePasswordDto>,
+) -> AppResult<TokenDto> {
+    check_password_policy(&state.config, &passwords.new_password)?;
+
+    let is_password_valid = state
+        .user_service
+        .validate_password_of_user_id(token.id, passwords.current_password)
+        .await;
+
+    if !is_password_valid {
+        return Err(AuthError::InvalidCredentials.into());
+    }
+
+    state
+        .user_service
+        .change_password(token.id, passwords.new_password)
+        .await
+        .ok_or(AppError::InternalServerError)?;
+
+    state.auth_token_service.revoke_tokens_for(token.id).await;
+    state
+        .refresh_token_service
+        .revoke_tokens_for(token.id)
+        .await;
+
+    let user = state
+        .user_service
+        .get_profile_by_id(token.id)
+        .await
+        .ok_or(AppError::InternalServerError)?;
+
+    AppOk(issue_tokens(&state, user).await).into()
+}
+
+/// Refreshes the token pair.
+///
+/// Exchanges a refresh token for a new token and refresh token. Each refresh
+/// token can be used only once; reusing a refresh token revokes every refresh
+/// token rotated from the same login. Banned and suspended users are
+/// rejected.
+#[utoipa::path(
+    post,
+    path = "/refresh",
+    request_body = RefreshTokenDto,
+    responses(
+        (status = OK, description = "New token pair", body = TokenDto),
+        error_examples::UnauthorizedDto,
+        error_examples::ForbiddenDto,
+    ),
+)]
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(RefreshTokenDto { refresh_token }): Json<RefreshTokenDto>,
+) -> AppResult<TokenDto> {
+    let Some((successor, refresh_token)) = state.refresh_token_service.rotate(&refresh_token).await
+    else {
+        return Err(AuthError::InvalidRefreshToken.into());
+    };
+
+    let Some(user) = state.user_service.get_profile_by_id(successor.id).await else {
+        return Err(AuthError::InvalidRefreshToken.into());
+    };
+
+    if UserFlag::Banned.is_set(&user.flags) {
+        return Err(AuthError::AccountBanned.into());
+    }
+
+    if let Some(remaining) = state.moderation_service.get_suspension(user.id).await {
+        return Err(AuthError::AccountSuspended(remaining).into());
+    }
+
+    let dto = TokenDto {
+        token: state
+            .auth_token_service
+            .sign(AuthToken::new(user.id, user.username.clone()))
+            .await,
+        refresh_token,
+        user,
+    };
+
+    AppOk(dto).into()
+}
+
+/// Requests a password reset.
+///
+/// Mails a single-use password reset token to the email if it is a verified
+/// email of an account. Succeeds regardless of whether the email exists, but
+/// requests are rate limited per client IP and per email.
+#[utoipa::path(
+    post,
+    path = "/password-reset/request",
+    request_body = PasswordResetRequestDto,
+    responses(
+        (status = OK, description = "Reset token sent if the email is verified"),
+        error_examples::TooManyAttemptsDto,
+    ),
+)]
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Json(PasswordResetRequestDto { email }): Json<PasswordResetRequestDto>,
+) -> Result<StatusCode, AppError> {
+    let email = email.trim().to_lowercase();
+
+    check_mail_rate_limit(&state, ip, &email).await?;
+
+    let service = state.password_reset_service.clone();
+
+    // Respond without waiting, so response time does not reveal whether the
+    // email exists.
+    tokio::spawn(async move { service.request_reset(&email).await });
+
+    Ok(StatusCode::OK)
+}
+
+/// Resets the password.
+///
+/// Sets a new password using the token mailed by password reset request, and
+/// revokes all existing tokens of the account.
+#[utoipa::path(
+    post,
+    path = "/password-reset/confirm",
+    request_body = PasswordResetConfirmDto,
+    responses(
+        (status = NO_CONTENT, description = "Password changed"),
+        error_examples::PasswordResetFailedDto,
+        error_examples::InappropriatePasswordOrUsernameDto,
+    ),
+)]
+pub async fn confirm_password_reset(
+    State(state): State<AppState>,
+    Json(reset): Json<PasswordResetConfirmDto>,
+) -> Result<StatusCode, AppError> {
+    check_password_policy(&state.config, &reset.new_password)?;
+
+    let Some(user_id) = state
+        .password_reset_service
+        .consume_token(&reset.token)
+        .await
+    else {
+        return Err(AuthError::InvalidResetToken.into());
+    };
+
+    state
+        .user_service
+        .change_password(user_id, reset.new_password)
+        .await
+        .ok_or(AppError::InternalServerError)?;
+
+    state.auth_token_service.revoke_tokens_for(user_id).await;
+    state.refresh_token_service.revoke_tokens_for(user_id).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,175 @@
+use crate::{
+    AppState,
+    dto::{
+        auth,
+        emails::{
+            AddEmailDto, EmailDto, EmailError, MAX_EMAILS_PER_USER, VerifyEmailDto, error_examples,
+        },
+    },
+    extract::{Auth, ClientIp},
+    handlers::auth_handler::check_mail_rate_limit,
+    response::{AppError, AppOk, AppResult},
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use validator::Validate;
+
+/// Lists emails of the account.
+///
+/// Returns every email attached to the authenticated user's account.
+#[utoipa::path(
+    get,
+    path = "",
+    responses(
+        (status = OK, description = "Email list", body = Vec<EmailDto>),
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_emails(State(state): State<AppState>, Auth(token): Auth) -> Json<Vec<EmailDto>> {
+    Json(state.email_service.get_emails_of_user(token.id).await)
+}
+
+/// Adds an email to the account.
+///
+/// The email is added as unverified, and a verification token is mailed to
+/// it. Emails verified by another account are added the same way, but cannot
+/// be verified. Mails are rate limited per client IP and per email.
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = AddEmailDto,
+    responses(
+        (status = OK, description = "Added email", body = EmailDto),
+        error_examples::InappropriateEmailDto,
+        error_examples::EmailTakenDto,
+        auth::error_examples::UnauthorizedDto,
+        auth::error_examples::TooManyAttemptsDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn add_email(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    ClientIp(ip): ClientIp,
+    Json(mut email): Json<AddEmailDto>,
+) -> AppResult<EmailDto> {
+    email.email = email.email.trim().to_lowercase();
+
+    if email.validate().is_err() {
+        return Err(EmailError::InvalidEmail.into());
+    }
+
+    let email_count = state
+        .email_service
+        .count_emails_of_user(token.id)
+        .await
+        .ok_or(AppError::InternalServerError)?;
+
+    if email_count >= MAX_EMAILS_PER_USER {
+        return Err(EmailError::TooManyEmails.into());
+    }
+
+    check_mail_rate_limit(&state, ip, &email.email).await?;
+
+    if let Some(email) = state.email_service.add_email(token.id, email.email).await {
+        AppOk(email).into()
+    } else {
+        Err(EmailError::EmailTaken.into())
+    }
+}
+
+/// Verifies an email.
+///
+/// Marks the email as verified using the token mailed to it. Tokens can be
+/// used only once.
+#[utoipa::path(
+    post,
+    path = "/verify",
+    request_body = VerifyEmailDto,
+    responses(
+        (status = OK, description = "Verified email", body = EmailDto),
+        error_examples::VerificationFailedDto,
+    ),
+)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(VerifyEmailDto { token }): Json<VerifyEmailDto>,
+) -> AppResult<EmailDto> {
+    if let Some(email) = state.email_service.verify_email(&token).await {
+        AppOk(email).into()
+    } else {
+        Err(EmailError::InvalidVerificationToken.into())
+    }
+}
+
+/// Resends verification token.
+///
+/// Mails a new verification token to an unverified email of the account.
+/// Mails are rate limited per client IP and per email.
+#[utoipa::path(
+    post,
+    path = "/{id}/verification",
+    responses(
+        (status = NO_CONTENT, description = "Verification token sent"),
+        error_examples::EmailNotFoundDto,
+        error_examples::EmailTakenDto,
+        auth::error_examples::UnauthorizedDto,
+        auth::error_examples::TooManyAttemptsDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn resend_verification(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    ClientIp(ip): ClientIp,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    let email = match state.email_service.get_email_by_id(id).await {
+        Some(email) if email.user_id == token.id => email,
+        _ => return Err(EmailError::EmailNotFound.into()),
+    };
+
+    if email.is_verified {
+        return Err(EmailError::AlreadyVerified.into());
+    }
+
+    check_mail_rate_limit(&state, ip, &email.email).await?;
+
+    if state
+        .email_service
+        .send_verification(&email)
+        .await
+        .is_some()
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::InternalServerError)
+    }
+}
+
+/// Removes an email from the account.
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    responses(
+        (status = NO_CONTENT, description = "Email removed"),
+        error_examples::EmailNotFoundDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn delete_email(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    match state.email_service.delete_email(id, token.id).await {
+        Some(true) => Ok(StatusCode::NO_CONTENT),
+        Some(false) => Err(EmailError::EmailNotFound.into()),
+        None => Err(AppError::InternalServerError),
+    }
+}
@@ -1,6 +1,9 @@
 /// Account creation and login handlers
 pub mod auth_handler;
 
+/// Email handlers
+pub mod email_handler;
+
 /// Post handlers
 pub mod post_handler;
 
@@ -9,3 +12,15 @@ pub mod user_handler;
 
 /// Thread handlers
 pub mod thread_handler;
+
+/// Home timeline handlers
+pub mod timeline_handler;
+
+/// Admin handlers
+pub mod admin_handler;
+
+/// Moderation handlers
+pub mod mod_handler;
+
+/// Attachment handlers
+pub mod attachment_handler;
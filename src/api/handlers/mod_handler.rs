@@ -0,0 +1,334 @@
+use crate::{
+    AppState,
+    dto::{
+        PagitationQuery, auth,
+        moderation::{
+            self, AuditEntryDto, MAX_SUSPENSION_DURATION, ModerationError, ModerationReasonDto,
+            SuspendUserDto, validate_reason,
+        },
+        posts::{self, PostError},
+        report::{ReportDto, ReportGroupDto, ReportQueueQuery},
+        user::{self, UserError},
+    },
+    entity::{self, ReportTarget},
+    extract::{Authorized, Moderator},
+    response::{AppError, AppOk, AppResult},
+};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+
+async fn set_post_hidden(
+    state: &AppState,
+    actor_id: i64,
+    post_id: i64,
+    hidden: bool,
+    reason: String,
+) -> Result<StatusCode, AppError> {
+    validate_reason(&reason)?;
+
+    if state.moderation_service.get_post(post_id).await.is_none() {
+        return Err(PostError::PostNotFound.into());
+    }
+
+    state
+        .moderation_service
+        .set_post_hidden(actor_id, post_id, hidden, reason)
+        .await
+        .ok_or(AppError::InternalServerError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Checks whether the target exists and can be moderated by the caller.
+async fn check_target(
+    state: &AppState,
+    moderator: &entity::User,
+    user_id: i64,
+) -> Result<(), AppError> {
+    let Some(target) = state.user_service.get_user_entity_by_id(user_id).await else {
+        return Err(UserError::UserNotFound.into());
+    };
+
+    if target.is_moderator() && !moderator.is_admin() {
+        return Err(ModerationError::CannotModerateUser.into());
+    }
+
+    Ok(())
+}
+
+/// Hides a post.
+///
+/// Hidden posts are excluded from post lookups, threads, timelines and hot
+/// posts. The reason is recorded in the audit log.
+#[utoipa::path(
+    put,
+    path = "/posts/{id}/hide",
+    request_body = ModerationReasonDto,
+    responses(
+        (status = NO_CONTENT, description = "Post hidden"),
+        moderation::error_examples::InappropriateModerationDto,
+        posts::error_examples::PostNotFoundDto,
+        auth::error_examples::ForbiddenDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn hide_post(
+    State(state): State<AppState>,
+    Authorized(moderator, _): Authorized<Moderator>,
+    Path(id): Path<i64>,
+    Json(ModerationReasonDto { reason }): Json<ModerationReasonDto>,
+) -> Result<StatusCode, AppError> {
+    set_post_hidden(&state, moderator.id, id, true, reason).await
+}
+
+/// Unhides a post.
+///
+/// Makes a hidden post visible again.
+#[utoipa::path(
+    delete,
+    path = "/posts/{id}/hide",
+    request_body = ModerationReasonDto,
+    responses(
+        (status = NO_CONTENT, description = "Post unhidden"),
+        moderation::error_examples::InappropriateModerationDto,
+        posts::error_examples::PostNotFoundDto,
+        auth::error_examples::ForbiddenDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn unhide_post(
+    State(state): State<AppState>,
+    Authorized(moderator, _): Authorized<Moderator>,
+    Path(id): Path<i64>,
+    Json(ModerationReasonDto { reason }): Json<ModerationReasonDto>,
+) -> Result<StatusCode, AppError> {
+    set_post_hidden(&state, moderator.id, id, false, reason).await
+}
+
+/// Removes a post.
+///
+/// Deletes the post regardless of its author. Replies to the post are kept.
+#[utoipa::path(
+    delete,
+    path = "/posts/{id}",
+    request_body = ModerationReasonDto,
+    responses(
+        (status = NO_CONTENT, description = "Post removed"),
+        moderation::error_examples::InappropriateModerationDto,
+        posts::error_examples::PostNotFoundDto,
+        auth::error_examples::ForbiddenDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn remove_post(
+    State(state): State<AppState>,
+    Authorized(moderator, _): Authorized<Moderator>,
+    Path(id): Path<i64>,
+    Json(ModerationReasonDto { reason }): Json<ModerationReasonDto>,
+) -> Result<StatusCode, AppError> {
+    validate_reason(&reason)?;
+
+    if state
+        .moderation_service
+        .remove_post(moderator.id, id, reason)
+        .await
+        .is_some()
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(PostError::PostNotFound.into())
+    }
+}
+
+/// Suspends an user.
+///
+/// Suspended users cannot log in or use authenticated endpoints until the
+/// suspension ends. Only admins can suspend moderators.
+#[utoipa::path(
+    post,
+    path = "/users/{id}/suspend",
+    request_body = SuspendUserDto,
+    responses(
+        (status = NO_CONTENT, description = "User suspended"),
+        moderation::error_examples::InappropriateModerationDto,
+        moderation::error_examples::CannotModerateDto,
+        user::error_examples::UserNotFoundDto,
+        auth::error_examples::ForbiddenDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn suspend_user(
+    State(state): State<AppState>,
+    Authorized(moderator, _): Authorized<Moderator>,
+    Path(id): Path<i64>,
+    Json(SuspendUserDto { duration, reason }): Json<SuspendUserDto>,
+) -> Result<StatusCode, AppError> {
+    validate_reason(&reason)?;
+
+    if !(1..=MAX_SUSPENSION_DURATION).contains(&duration) {
+        return Err(ModerationError::InvalidDuration.into());
+    }
+
+    check_target(&state, &moderator, id).await?;
+
+    state
+        .moderation_service
+        .suspend_user(moderator.id, id, duration, reason)
+        .await
+        .ok_or(AppError::InternalServerError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lifts suspension of an user.
+///
+/// Lifting suspension of an user that is not suspended has no effect.
+#[utoipa::path(
+    delete,
+    path = "/users/{id}/suspend",
+    request_body = ModerationReasonDto,
+    responses(
+        (status = NO_CONTENT, description = "Suspension lifted"),
+        moderation::error_examples::InappropriateModerationDto,
+        moderation::error_examples::CannotModerateDto,
+        user::error_examples::UserNotFoundDto,
+        auth::error_examples::ForbiddenDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn unsuspend_user(
+    State(state): State<AppState>,
+    Authorized(moderator, _): Authorized<Moderator>,
+    Path(id): Path<i64>,
+    Json(ModerationReasonDto { reason }): Json<ModerationReasonDto>,
+) -> Result<StatusCode, AppError> {
+    validate_reason(&reason)?;
+    check_target(&state, &moderator, id).await?;
+
+    state
+        .moderation_service
+        .unsuspend_user(moderator.id, id, reason)
+        .await
+        .ok_or(AppError::InternalServerError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Audit log
+///
+/// Returns moderation and administration actions, latest first.
+#[utoipa::path(
+    get,
+    path = "/audit",
+    responses(
+        (status = OK, description = "Audit log entries", body = Vec<AuditEntryDto>),
+        auth::error_examples::ForbiddenDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    params(PagitationQuery),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_audit_log(
+    State(state): State<AppState>,
+    _: Authorized<Moderator>,
+    Query(PagitationQuery { limit, before }): Query<PagitationQuery>,
+) -> AppResult<Vec<AuditEntryDto>> {
+    AppOk(state.moderation_service.get_audit_log(limit, before).await).into()
+}
+
+/// Report queue
+///
+/// Returns reported posts and users grouped by target, the most reported
+/// targets first. Pass `report_count` and `last_report_id` of the last target
+/// as `before_count` and `before` to fetch the next page.
+#[utoipa::path(
+    get,
+    path = "/reports",
+    responses(
+        (status = OK, description = "Reported targets", body = Vec<ReportGroupDto>),
+        auth::error_examples::ForbiddenDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    params(ReportQueueQuery),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_report_queue(
+    State(state): State<AppState>,
+    _: Authorized<Moderator>,
+    Query(query): Query<ReportQueueQuery>,
+) -> AppResult<Vec<ReportGroupDto>> {
+    let before = query
+        .before
+        .map(|before| (query.before_count.unwrap_or(u64::MAX), before));
+
+    AppOk(
+        state
+            .report_service
+            .get_report_queue(query.target_type, query.limit, before)
+            .await,
+    )
+    .into()
+}
+
+/// Reports of a target
+///
+/// Returns the latest reports of a post or user with their details.
+#[utoipa::path(
+    get,
+    path = "/reports/{target_type}/{id}",
+    responses(
+        (status = OK, description = "Reports", body = Vec<ReportDto>),
+        auth::error_examples::ForbiddenDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_reports(
+    State(state): State<AppState>,
+    _: Authorized<Moderator>,
+    Path((target_type, id)): Path<(ReportTarget, i64)>,
+) -> AppResult<Vec<ReportDto>> {
+    AppOk(
+        state
+            .report_service
+            .get_reports_of_target(target_type, id)
+            .await,
+    )
+    .into()
+}
+
+/// Dismisses reports of a target.
+///
+/// Removes the target from the report queue. Dismissing a target without
+/// reports has no effect.
+#[utoipa::path(
+    delete,
+    path = "/reports/{target_type}/{id}",
+    responses(
+        (status = NO_CONTENT, description = "Reports dismissed"),
+        auth::error_examples::ForbiddenDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn dismiss_reports(
+    State(state): State<AppState>,
+    Authorized(moderator, _): Authorized<Moderator>,
+    Path((target_type, id)): Path<(ReportTarget, i64)>,
+) -> Result<StatusCode, AppError> {
+    state
+        .report_service
+        .dismiss_reports(moderator.id, target_type, id)
+        .await
+        .ok_or(AppError::InternalServerError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,9 +1,42 @@
 use crate::{
     AppState,
-    dto::posts::{PostDto, PostError, PostStatsDto, error_examples},
-    response::{AppOk, AppResult},
+    dto::{
+        PagitationQuery, auth,
+        posts::{
+            CreatePostDto, EditPostDto, PostContextDto, PostDto, PostError, PostStatsDto,
+            ReplyTreeDto, ReplyTreeQuery, error_examples, validate_post,
+        },
+        report::{self, CreateReportDto, ReportError, validate_details},
+        user::{self, UserDto, UserError},
+    },
+    entity::ReportTarget,
+    extract::Auth,
+    response::{AppError, AppOk, AppResult},
 };
-use axum::extract::{Path, State};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+
+/// Checks that every attachment exists and has been uploaded by the user.
+async fn check_attachments(
+    state: &AppState,
+    user_id: i64,
+    attachments: &[i64],
+) -> Result<(), PostError> {
+    for &attachment_id in attachments {
+        if !state
+            .user_service
+            .owns_attachment(user_id, attachment_id)
+            .await
+        {
+            return Err(PostError::AttachmentNotOwned);
+        }
+    }
+
+    Ok(())
+}
 
 /// Gets a post by ID.
 ///
@@ -49,3 +82,326 @@ pub async fn get_post_stats_by_id(
         Err(PostError::PostNotFound.into())
     }
 }
+
+/// Sends a post.
+///
+/// Creates a post and an empty thread for its replies. The post is sent to
+/// the main thread if `thread_id` is omitted. Users cannot post on the walls
+/// of the users that block them. Attachments must be uploaded by the caller.
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = CreatePostDto,
+    responses(
+        (status = OK, description = "Created post", body = PostDto),
+        error_examples::InappropriatePostDto,
+        error_examples::ThreadNotFoundDto,
+        user::error_examples::BlockedByUserDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn create_post(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Json(post): Json<CreatePostDto>,
+) -> AppResult<PostDto> {
+    validate_post(&post.content, &post.attachments)?;
+    check_attachments(&state, token.id, &post.attachments).await?;
+
+    if let Some(thread_id) = post.thread_id {
+        if !state.post_service.thread_exists(thread_id).await {
+            return Err(PostError::ThreadNotFound.into());
+        }
+
+        if state
+            .user_service
+            .is_blocked_from_thread(thread_id, token.id)
+            .await
+        {
+            return Err(UserError::BlockedByUser.into());
+        }
+    }
+
+    if let Some(post) = state
+        .post_service
+        .create_post(token.id, post.thread_id, post.content, post.attachments)
+        .await
+    {
+        AppOk(post).into()
+    } else {
+        Err(AppError::InternalServerError)
+    }
+}
+
+/// Edits a post.
+///
+/// Replaces content and/or attachments of a post and marks it as edited.
+/// Only the author of the post can edit it, attachments must be uploaded by
+/// the author.
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    request_body = EditPostDto,
+    responses(
+        (status = OK, description = "Edited post", body = PostDto),
+        error_examples::PostNotFoundDto,
+        error_examples::NotPostAuthorDto,
+        error_examples::InappropriatePostDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn edit_post(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+    Json(edit): Json<EditPostDto>,
+) -> AppResult<PostDto> {
+    let Some(post) = state.post_service.get_post_by_id(id).await else {
+        return Err(PostError::PostNotFound.into());
+    };
+
+    if post.user_id != token.id {
+        return Err(PostError::NotPostAuthor.into());
+    }
+
+    let content = edit.content.unwrap_or(post.content);
+    let attachments = edit.attachments.unwrap_or(post.attachments);
+
+    validate_post(&content, &attachments)?;
+    check_attachments(&state, token.id, &attachments).await?;
+
+    if let Some(post) = state
+        .post_service
+        .edit_post(id, &content, &attachments)
+        .await
+    {
+        AppOk(post).into()
+    } else {
+        Err(PostError::PostNotFound.into())
+    }
+}
+
+/// Deletes a post.
+///
+/// Only the author of the post can delete it. Replies to the post are kept.
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    responses(
+        (status = NO_CONTENT, description = "Post deleted"),
+        error_examples::PostNotFoundDto,
+        error_examples::NotPostAuthorDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn delete_post(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    let Some(post) = state.post_service.get_post_by_id(id).await else {
+        return Err(PostError::PostNotFound.into());
+    };
+
+    if post.user_id != token.id {
+        return Err(PostError::NotPostAuthor.into());
+    }
+
+    if state.post_service.delete_post(id).await.is_some() {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(PostError::PostNotFound.into())
+    }
+}
+
+/// Likes a post.
+///
+/// Liking a post that is already liked by the user has no effect.
+#[utoipa::path(
+    put,
+    path = "/{id}/like",
+    responses(
+        (status = NO_CONTENT, description = "Post liked"),
+        error_examples::PostNotFoundDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn like_post(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    if state.post_service.get_post_by_id(id).await.is_none() {
+        return Err(PostError::PostNotFound.into());
+    }
+
+    if state.post_service.like_post(token.id, id).await.is_some() {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::InternalServerError)
+    }
+}
+
+/// Removes like from a post.
+///
+/// Unliking a post that is not liked by the user has no effect.
+#[utoipa::path(
+    delete,
+    path = "/{id}/like",
+    responses(
+        (status = NO_CONTENT, description = "Post unliked"),
+        error_examples::PostNotFoundDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn unlike_post(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    if state.post_service.get_post_by_id(id).await.is_none() {
+        return Err(PostError::PostNotFound.into());
+    }
+
+    if state.post_service.unlike_post(token.id, id).await.is_some() {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::InternalServerError)
+    }
+}
+
+/// Reports a post.
+///
+/// Each user can report a post once. Reports are reviewed by moderators.
+#[utoipa::path(
+    post,
+    path = "/{id}/report",
+    request_body = CreateReportDto,
+    responses(
+        (status = NO_CONTENT, description = "Post reported"),
+        error_examples::PostNotFoundDto,
+        report::error_examples::InappropriateReportDto,
+        report::error_examples::AlreadyReportedDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn report_post(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+    Json(CreateReportDto { reason, details }): Json<CreateReportDto>,
+) -> Result<StatusCode, AppError> {
+    let details = validate_details(details)?;
+
+    let Some(post) = state.post_service.get_post_by_id(id).await else {
+        return Err(PostError::PostNotFound.into());
+    };
+
+    if post.user_id == token.id {
+        return Err(ReportError::CannotReportSelf.into());
+    }
+
+    match state
+        .report_service
+        .report(token.id, ReportTarget::Post, id, reason, details)
+        .await
+    {
+        Some(true) => Ok(StatusCode::NO_CONTENT),
+        Some(false) => Err(ReportError::AlreadyReported.into()),
+        None => Err(AppError::InternalServerError),
+    }
+}
+
+/// Users that liked a post
+///
+/// Returns list of the users that liked the post.
+#[utoipa::path(
+    get,
+    path = "/{id}/likes",
+    responses(
+        (status = OK, description = "User list", body = Vec<UserDto>),
+    ),
+    params(PagitationQuery)
+)]
+pub async fn get_likes(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(PagitationQuery { limit, before }): Query<PagitationQuery>,
+) -> Json<Vec<UserDto>> {
+    Json(state.post_service.get_likes(id, limit, before).await)
+}
+
+/// Gets replies of a post as a tree.
+///
+/// Returns the latest replies of the post, each with its latest replies down
+/// to `depth` levels. Branches with replies left out have a continuation
+/// cursor for `/posts/{id}/replies` of the reply. Posts of the users blocked
+/// or muted by the caller are excluded.
+#[utoipa::path(
+    get,
+    path = "/{id}/replies",
+    responses(
+        (status = OK, description = "Reply tree", body = Vec<ReplyTreeDto>),
+        error_examples::PostNotFoundDto
+    ),
+    params(ReplyTreeQuery),
+    security((), ("bearer_auth" = [])),
+)]
+pub async fn get_replies(
+    State(state): State<AppState>,
+    auth: Option<Auth>,
+    Path(id): Path<i64>,
+    Query(ReplyTreeQuery {
+        depth,
+        limit,
+        before,
+    }): Query<ReplyTreeQuery>,
+) -> AppResult<Vec<ReplyTreeDto>> {
+    let Some(post) = state.post_service.get_post_by_id(id).await else {
+        return Err(PostError::PostNotFound.into());
+    };
+
+    AppOk(
+        state
+            .post_service
+            .get_reply_tree(
+                post.replies_thread_id,
+                auth.map(|Auth(token)| token.id),
+                depth,
+                limit,
+                before,
+            )
+            .await,
+    )
+    .into()
+}
+
+/// Gets context of a reply.
+///
+/// Returns the posts that the reply is replying to, from the root post down
+/// to its direct parent. Indicates whether the root post is on the main
+/// thread or a profile wall.
+#[utoipa::path(
+    get,
+    path = "/{id}/context",
+    responses(
+        (status = OK, description = "Post context", body = PostContextDto),
+        error_examples::PostNotFoundDto
+    ),
+)]
+pub async fn get_post_context(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<PostContextDto> {
+    let Some(post) = state.post_service.get_post_by_id(id).await else {
+        return Err(PostError::PostNotFound.into());
+    };
+
+    AppOk(state.post_service.get_post_context(&post).await).into()
+}
@@ -1,6 +1,7 @@
 use crate::{
     AppState,
     dto::{PagitationQuery, TimePeriodQuery, posts::PostDto},
+    extract::Auth,
 };
 use axum::{
     Json,
@@ -9,94 +10,106 @@ use axum::{
 
 /// Gets latest posts on the main thread
 ///
-/// List of latest posts in main thread.
+/// List of latest posts in main thread. Posts of the users blocked or muted
+/// by the caller are excluded.
 #[utoipa::path(
     get,
     path = "/latest",
     responses(
         (status = OK, description = "Post list", body = Vec<PostDto>),
     ),
-    params(PagitationQuery)
+    params(PagitationQuery),
+    security((), ("bearer_auth" = [])),
 )]
 pub async fn get_latest_posts(
     State(state): State<AppState>,
+    auth: Option<Auth>,
     Query(PagitationQuery { limit, before }): Query<PagitationQuery>,
 ) -> Json<Vec<PostDto>> {
     Json(
         state
             .post_service
-            .get_latest_posts_of_thread(None, limit, before)
+            .get_latest_posts_of_thread(None, auth.map(|Auth(token)| token.id), limit, before)
             .await,
     )
 }
 
 /// Gets hot posts on the main thread
 ///
-/// List of hot posts in main thread.
+/// List of hot posts in main thread. Posts of the users blocked or muted by
+/// the caller are excluded.
 #[utoipa::path(
     get,
     path = "/hot",
     responses(
         (status = OK, description = "Post list", body = Vec<PostDto>),
     ),
-    params(TimePeriodQuery)
+    params(TimePeriodQuery),
+    security((), ("bearer_auth" = [])),
 )]
 pub async fn get_hot_posts(
     State(state): State<AppState>,
+    auth: Option<Auth>,
     Query(TimePeriodQuery { time_period }): Query<TimePeriodQuery>,
 ) -> Json<Vec<PostDto>> {
     Json(
         state
             .post_service
-            .get_hot_posts_of_thread(None, time_period)
+            .get_hot_posts_of_thread(None, auth.map(|Auth(token)| token.id), time_period)
             .await,
     )
 }
 
 /// Gets the posts in a thread
 ///
-/// List of posts in a thread.
+/// List of posts in a thread. Posts of the users blocked or muted by the
+/// caller are excluded.
 #[utoipa::path(
     get,
     path = "/{id}/latest",
     responses(
         (status = OK, description = "Post list", body = Vec<PostDto>),
     ),
-    params(PagitationQuery)
+    params(PagitationQuery),
+    security((), ("bearer_auth" = [])),
 )]
 pub async fn get_latest_posts_of_thread(
     State(state): State<AppState>,
+    auth: Option<Auth>,
     Path(id): Path<i64>,
     Query(PagitationQuery { limit, before }): Query<PagitationQuery>,
 ) -> Json<Vec<PostDto>> {
     Json(
         state
             .post_service
-            .get_latest_posts_of_thread(Some(id), limit, before)
+            .get_latest_posts_of_thread(Some(id), auth.map(|Auth(token)| token.id), limit, before)
             .await,
     )
 }
 
 /// Gets hot posts in a thread
 ///
-/// List of posts in a thread.
+/// List of posts in a thread. Posts of the users blocked or muted by the
+/// caller are excluded.
 #[utoipa::path(
     get,
     path = "/{id}/hot",
     responses(
         (status = OK, description = "Post list", body = Vec<PostDto>),
     ),
-    params(TimePeriodQuery)
+    params(TimePeriodQuery),
+    security((), ("bearer_auth" = [])),
 )]
 pub async fn get_hot_posts_of_thread(
     State(state): State<AppState>,
+    auth: Option<Auth>,
     Path(id): Path<i64>,
     Query(TimePeriodQuery { time_period }): Query<TimePeriodQuery>,
 ) -> Json<Vec<PostDto>> {
     Json(
         state
             .post_service
-            .get_hot_posts_of_thread(Some(id), time_period)
+            .get_hot_posts_of_thread(Some(id), auth.map(|Auth(token)| token.id), time_period)
             .await,
     )
 }
@@ -0,0 +1,35 @@
+use crate::{
+    AppState,
+    dto::{PagitationQuery, auth::error_examples, posts::PostDto},
+    extract::Auth,
+};
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+
+/// Gets home timeline of the caller
+///
+/// List of latest posts authored by the users that the caller follows.
+#[utoipa::path(
+    get,
+    path = "",
+    responses(
+        (status = OK, description = "Post list", body = Vec<PostDto>),
+        error_examples::UnauthorizedDto,
+    ),
+    params(PagitationQuery),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_timeline(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Query(PagitationQuery { limit, before }): Query<PagitationQuery>,
+) -> Json<Vec<PostDto>> {
+    Json(
+        state
+            .post_service
+            .get_timeline(token.id, limit, before)
+            .await,
+    )
+}
@@ -2,15 +2,44 @@ use crate::{
     AppState,
     dto::{
         PagitationQuery,
-        user::{FullProfileDto, UserDto, UserError, UserStatsDto, error_examples},
+        auth::{self, validate_username},
+        report::{self, CreateReportDto, ReportError, validate_details},
+        user::{
+            ChangeUsernameDto, DeleteAccountDto, EditProfileDto, FullProfileDto, MAX_BIO_LENGTH,
+            UserDto, UserError, UserStatsDto, error_examples,
+        },
     },
-    response::{AppOk, AppResult},
+    entity::ReportTarget,
+    extract::Auth,
+    response::{AppError, AppOk, AppResult},
 };
 use axum::{
     Json,
     extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Redirect, Response},
 };
 
+/// Fills relationship of the profile with the authenticated caller.
+async fn with_relationship(
+    state: &AppState,
+    auth: Option<Auth>,
+    mut profile: FullProfileDto,
+) -> FullProfileDto {
+    if let Some(Auth(token)) = auth
+        && token.id != profile.id
+    {
+        profile.relationship = Some(
+            state
+                .user_service
+                .get_relationship(token.id, profile.id)
+                .await,
+        );
+    }
+
+    profile
+}
+
 /// Gets an user by ID.
 ///
 /// Fetches one user from its ID.
@@ -35,33 +64,45 @@ pub async fn get_user_by_id(
 
 /// Gets an user by username.
 ///
-/// Fetches one user from its username.
+/// Fetches one user from its username. Usernames released in the username
+/// cooldown redirect to the user's current username.
 #[utoipa::path(
     get,
     path = "/@{username}",
     responses(
         (status = OK, description = "User object", body = UserDto),
+        (status = TEMPORARY_REDIRECT, description = "Redirect to the current username of the user that recently released this username"),
         error_examples::UserNotFoundDto
     ),
 )]
 pub async fn get_user_by_username(
     State(state): State<AppState>,
     Path(username): Path<String>,
-) -> AppResult<UserDto> {
+) -> Result<Response, AppError> {
     if username.len() > 20 || username.is_empty() {
         return Err(UserError::UserNotFound.into());
     }
 
     if let Some(user) = state.user_service.get_user_by_username(&username).await {
-        AppOk(user).into()
-    } else {
-        Err(UserError::UserNotFound.into())
+        return Ok(Json(user).into_response());
+    }
+
+    match state
+        .user_service
+        .get_user_by_previous_username(&username, state.config.username_cooldown)
+        .await
+    {
+        Some(user) => {
+            Ok(Redirect::temporary(&format!("/users/@{}", user.username)).into_response())
+        }
+        None => Err(UserError::UserNotFound.into()),
     }
 }
 
 /// Gets an user profile by user ID.
 ///
-/// Fetches one user's profile from user ID.
+/// Fetches one user's profile from user ID. Includes follow relationship with
+/// the caller if authenticated.
 #[utoipa::path(
     get,
     path = "/{id}/profile",
@@ -69,13 +110,15 @@ pub async fn get_user_by_username(
         (status = OK, description = "User profile object", body = FullProfileDto),
         error_examples::UserNotFoundDto
     ),
+    security((), ("bearer_auth" = [])),
 )]
 pub async fn get_profile_by_id(
     State(state): State<AppState>,
+    auth: Option<Auth>,
     Path(id): Path<i64>,
 ) -> AppResult<FullProfileDto> {
     if let Some(user) = state.user_service.get_profile_by_id(id).await {
-        AppOk(user).into()
+        AppOk(with_relationship(&state, auth, user).await).into()
     } else {
         Err(UserError::UserNotFound.into())
     }
@@ -83,7 +126,8 @@ pub async fn get_profile_by_id(
 
 /// Gets an user profile by username.
 ///
-/// Fetches one user's profile from username.
+/// Fetches one user's profile from username. Includes follow relationship
+/// with the caller if authenticated.
 #[utoipa::path(
     get,
     path = "/@{username}/profile",
@@ -91,9 +135,11 @@ pub async fn get_profile_by_id(
         (status = OK, description = "User profile object", body = FullProfileDto),
         error_examples::UserNotFoundDto
     ),
+    security((), ("bearer_auth" = [])),
 )]
 pub async fn get_profile_by_username(
     State(state): State<AppState>,
+    auth: Option<Auth>,
     Path(username): Path<String>,
 ) -> AppResult<FullProfileDto> {
     if username.len() > 20 || username.is_empty() {
@@ -101,7 +147,7 @@ pub async fn get_profile_by_username(
     }
 
     if let Some(user) = state.user_service.get_profile_by_username(&username).await {
-        AppOk(user).into()
+        AppOk(with_relationship(&state, auth, user).await).into()
     } else {
         Err(UserError::UserNotFound.into())
     }
@@ -131,38 +177,539 @@ pub async fn get_user_stats_by_id(
 
 /// User's follows
 ///
-/// Returns list of the user's follows
+/// Returns list of the user's follows, except the users blocked by the
+/// caller.
 #[utoipa::path(
     get,
     path = "/{id}/follows",
     responses(
         (status = OK, description = "User list", body = Vec<UserDto>),
     ),
-    params(PagitationQuery)
+    params(PagitationQuery),
+    security((), ("bearer_auth" = [])),
 )]
 pub async fn get_follows(
     State(state): State<AppState>,
+    auth: Option<Auth>,
     Path(id): Path<i64>,
     Query(PagitationQuery { limit, before }): Query<PagitationQuery>,
 ) -> Json<Vec<UserDto>> {
-    Json(state.user_service.get_follows(id, limit, before).await)
+    Json(
+        state
+            .user_service
+            .get_follows(id, auth.map(|Auth(token)| token.id), limit, before)
+            .await,
+    )
 }
 
 /// User's followers
 ///
-/// Returns list of the user's followers
+/// Returns list of the user's followers, except the users blocked by the
+/// caller.
 #[utoipa::path(
     get,
     path = "/{id}/followers",
     responses(
         (status = OK, description = "User list", body = Vec<UserDto>),
     ),
-    params(PagitationQuery)
+    params(PagitationQuery),
+    security((), ("bearer_auth" = [])),
 )]
 pub async fn get_followers(
     State(state): State<AppState>,
+    auth: Option<Auth>,
     Path(id): Path<i64>,
     Query(PagitationQuery { limit, before }): Query<PagitationQuery>,
 ) -> Json<Vec<UserDto>> {
-    Json(state.user_service.get_followers(id, limit, before).await)
+    Json(
+        state
+            .user_service
+            .get_followers(id, auth.map(|Auth(token)| token.id), limit, before)
+            .await,
+    )
+}
+
+/// Follows an user.
+///
+/// Authenticated user starts following the user. Users cannot follow the
+/// users that block them.
+#[utoipa::path(
+    put,
+    path = "/{id}/follow",
+    responses(
+        (status = NO_CONTENT, description = "User followed"),
+        error_examples::UserNotFoundDto,
+        error_examples::InappropriateFollowDto,
+        error_examples::BlockedByUserDto,
+        error_examples::FollowConflictDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn follow_user(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    if id == token.id {
+        return Err(UserError::CannotFollowSelf.into());
+    }
+
+    if state.user_service.get_user_by_id(id).await.is_none() {
+        return Err(UserError::UserNotFound.into());
+    }
+
+    if state.user_service.is_blocking(id, token.id).await {
+        return Err(UserError::BlockedByUser.into());
+    }
+
+    match state.user_service.follow_user(token.id, id).await {
+        Some(true) => Ok(StatusCode::NO_CONTENT),
+        Some(false) => Err(UserError::AlreadyFollowing.into()),
+        None => Err(AppError::InternalServerError),
+    }
+}
+
+/// Unfollows an user.
+///
+/// Authenticated user stops following the user.
+#[utoipa::path(
+    delete,
+    path = "/{id}/follow",
+    responses(
+        (status = NO_CONTENT, description = "User unfollowed"),
+        error_examples::FollowConflictDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn unfollow_user(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    match state.user_service.unfollow_user(token.id, id).await {
+        Some(true) => Ok(StatusCode::NO_CONTENT),
+        Some(false) => Err(UserError::NotFollowing.into()),
+        None => Err(AppError::InternalServerError),
+    }
+}
+
+/// Blocks an user.
+///
+/// Blocked users are excluded from the caller's thread and follow lists and
+/// cannot follow the caller or post on the caller's wall. Follow relations
+/// between the users are removed.
+#[utoipa::path(
+    put,
+    path = "/{id}/block",
+    responses(
+        (status = NO_CONTENT, description = "User blocked"),
+        error_examples::UserNotFoundDto,
+        error_examples::InappropriateBlockDto,
+        error_examples::BlockConflictDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn block_user(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    if id == token.id {
+        return Err(UserError::CannotBlockSelf.into());
+    }
+
+    if state.user_service.get_user_by_id(id).await.is_none() {
+        return Err(UserError::UserNotFound.into());
+    }
+
+    match state.user_service.block_user(token.id, id).await {
+        Some(true) => Ok(StatusCode::NO_CONTENT),
+        Some(false) => Err(UserError::AlreadyBlocking.into()),
+        None => Err(AppError::InternalServerError),
+    }
+}
+
+/// Unblocks an user.
+///
+/// Removed follow relations are not restored.
+#[utoipa::path(
+    delete,
+    path = "/{id}/block",
+    responses(
+        (status = NO_CONTENT, description = "User unblocked"),
+        error_examples::BlockConflictDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn unblock_user(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    match state.user_service.unblock_user(token.id, id).await {
+        Some(true) => Ok(StatusCode::NO_CONTENT),
+        Some(false) => Err(UserError::NotBlocking.into()),
+        None => Err(AppError::InternalServerError),
+    }
+}
+
+/// Mutes an user.
+///
+/// Posts of muted users are excluded from the caller's threads and timeline.
+/// Unlike blocking, muted users can still interact with the caller.
+#[utoipa::path(
+    put,
+    path = "/{id}/mute",
+    responses(
+        (status = NO_CONTENT, description = "User muted"),
+        error_examples::UserNotFoundDto,
+        error_examples::InappropriateBlockDto,
+        error_examples::BlockConflictDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn mute_user(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    if id == token.id {
+        return Err(UserError::CannotMuteSelf.into());
+    }
+
+    if state.user_service.get_user_by_id(id).await.is_none() {
+        return Err(UserError::UserNotFound.into());
+    }
+
+    match state.user_service.mute_user(token.id, id).await {
+        Some(true) => Ok(StatusCode::NO_CONTENT),
+        Some(false) => Err(UserError::AlreadyMuting.into()),
+        None => Err(AppError::InternalServerError),
+    }
+}
+
+/// Unmutes an user.
+///
+/// Authenticated user stops muting the user.
+#[utoipa::path(
+    delete,
+    path = "/{id}/mute",
+    responses(
+        (status = NO_CONTENT, description = "User unmuted"),
+        error_examples::BlockConflictDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn unmute_user(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError> {
+    match state.user_service.unmute_user(token.id, id).await {
+        Some(true) => Ok(StatusCode::NO_CONTENT),
+        Some(false) => Err(UserError::NotMuting.into()),
+        None => Err(AppError::InternalServerError),
+    }
+}
+
+/// Reports an user.
+///
+/// Each user can report another user once. Reports are reviewed by
+/// moderators.
+#[utoipa::path(
+    post,
+    path = "/{id}/report",
+    request_body = CreateReportDto,
+    responses(
+        (status = NO_CONTENT, description = "User reported"),
+        error_examples::UserNotFoundDto,
+        report::error_examples::InappropriateReportDto,
+        report::error_examples::AlreadyReportedDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn report_user(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Path(id): Path<i64>,
+    Json(CreateReportDto { reason, details }): Json<CreateReportDto>,
+) -> Result<StatusCode, AppError> {
+    let details = validate_details(details)?;
+
+    if id == token.id {
+        return Err(ReportError::CannotReportSelf.into());
+    }
+
+    if state.user_service.get_user_by_id(id).await.is_none() {
+        return Err(UserError::UserNotFound.into());
+    }
+
+    match state
+        .report_service
+        .report(token.id, ReportTarget::User, id, reason, details)
+        .await
+    {
+        Some(true) => Ok(StatusCode::NO_CONTENT),
+        Some(false) => Err(ReportError::AlreadyReported.into()),
+        None => Err(AppError::InternalServerError),
+    }
+}
+
+/// Gets the caller.
+///
+/// Fetches the authenticated user.
+#[utoipa::path(
+    get,
+    path = "/@me",
+    responses(
+        (status = OK, description = "User object", body = UserDto),
+        error_examples::UserNotFoundDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_me(State(state): State<AppState>, Auth(token): Auth) -> AppResult<UserDto> {
+    if let Some(user) = state.user_service.get_user_by_id(token.id).await {
+        AppOk(user).into()
+    } else {
+        Err(UserError::UserNotFound.into())
+    }
+}
+
+/// Gets the caller's profile.
+///
+/// Fetches the authenticated user's profile.
+#[utoipa::path(
+    get,
+    path = "/@me/profile",
+    responses(
+        (status = OK, description = "User profile object", body = FullProfileDto),
+        error_examples::UserNotFoundDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_my_profile(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+) -> AppResult<FullProfileDto> {
+    if let Some(profile) = state.user_service.get_profile_by_id(token.id).await {
+        AppOk(profile).into()
+    } else {
+        Err(UserError::UserNotFound.into())
+    }
+}
+
+/// Gets the caller's stats.
+///
+/// Fetches the authenticated user's stats.
+#[utoipa::path(
+    get,
+    path = "/@me/stats",
+    responses(
+        (status = OK, description = "User stats object", body = UserStatsDto),
+        error_examples::UserNotFoundDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_my_stats(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+) -> AppResult<UserStatsDto> {
+    if let Some(user_stats) = state.user_service.get_user_stats_by_id(token.id).await {
+        AppOk(user_stats).into()
+    } else {
+        Err(UserError::UserNotFound.into())
+    }
+}
+
+/// Users blocked by the caller
+///
+/// Returns list of the users that the authenticated user blocks.
+#[utoipa::path(
+    get,
+    path = "/@me/blocks",
+    responses(
+        (status = OK, description = "User list", body = Vec<UserDto>),
+        auth::error_examples::UnauthorizedDto,
+    ),
+    params(PagitationQuery),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_my_blocks(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Query(PagitationQuery { limit, before }): Query<PagitationQuery>,
+) -> Json<Vec<UserDto>> {
+    Json(state.user_service.get_blocks(token.id, limit, before).await)
+}
+
+/// Users muted by the caller
+///
+/// Returns list of the users that the authenticated user mutes.
+#[utoipa::path(
+    get,
+    path = "/@me/mutes",
+    responses(
+        (status = OK, description = "User list", body = Vec<UserDto>),
+        auth::error_examples::UnauthorizedDto,
+    ),
+    params(PagitationQuery),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_my_mutes(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Query(PagitationQuery { limit, before }): Query<PagitationQuery>,
+) -> Json<Vec<UserDto>> {
+    Json(state.user_service.get_mutes(token.id, limit, before).await)
+}
+
+/// Edits the caller's profile.
+///
+/// Updates bio, avatar and banner of the authenticated user. Avatar and
+/// banner must be attachments uploaded by the caller.
+#[utoipa::path(
+    patch,
+    path = "/@me/profile",
+    request_body = EditProfileDto,
+    responses(
+        (status = OK, description = "Updated profile object", body = FullProfileDto),
+        error_examples::InappropriateProfileDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn edit_profile(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Json(profile): Json<EditProfileDto>,
+) -> AppResult<FullProfileDto> {
+    if let Some(bio) = &profile.bio
+        && bio.chars().count() > MAX_BIO_LENGTH
+    {
+        return Err(UserError::BioTooLong.into());
+    }
+
+    for attachment_id in [profile.avatar_id, profile.banner_id]
+        .into_iter()
+        .flatten()
+        .flatten()
+    {
+        if !state
+            .user_service
+            .owns_attachment(token.id, attachment_id)
+            .await
+        {
+            return Err(UserError::AttachmentNotOwned.into());
+        }
+    }
+
+    match state
+        .user_service
+        .update_profile(
+            token.id,
+            profile.bio.as_deref(),
+            profile.avatar_id,
+            profile.banner_id,
+        )
+        .await
+    {
+        Some(profile) => AppOk(profile).into(),
+        None => Err(UserError::UserNotFound.into()),
+    }
+}
+
+/// Changes the caller's username.
+///
+/// The new username is validated with the registration rules. The old
+/// username is kept reserved for the caller during the username cooldown,
+/// or until the caller changes the username again.
+#[utoipa::path(
+    post,
+    path = "/@me/username",
+    request_body = ChangeUsernameDto,
+    responses(
+        (status = OK, description = "Updated profile object", body = FullProfileDto),
+        auth::error_examples::InappropriatePasswordOrUsernameDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn change_username(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Json(ChangeUsernameDto { username }): Json<ChangeUsernameDto>,
+) -> AppResult<FullProfileDto> {
+    let username = validate_username(&username)?;
+
+    if state
+        .user_service
+        .is_username_reserved(&username, Some(token.id), state.config.username_cooldown)
+        .await
+    {
+        return Err(auth::AuthError::UsernameTaken(username).into());
+    }
+
+    match state
+        .user_service
+        .change_username(token.id, &username)
+        .await
+    {
+        Some(profile) => AppOk(profile).into(),
+        None => Err(auth::AuthError::UsernameTaken(username).into()),
+    }
+}
+
+/// Deletes the caller's account.
+///
+/// Requires the current password. Emails, likes and follows of the account
+/// are removed, posts are anonymised or deleted depending on the server's
+/// retention policy and every token of the account is revoked. The account
+/// is purged and its username released after a grace period.
+#[utoipa::path(
+    delete,
+    path = "/@me",
+    request_body = DeleteAccountDto,
+    responses(
+        (status = NO_CONTENT, description = "Account deleted"),
+        auth::error_examples::InvalidCredentialsDto,
+        auth::error_examples::UnauthorizedDto,
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn delete_account(
+    State(state): State<AppState>,
+    Auth(token): Auth,
+    Json(DeleteAccountDto { password }): Json<DeleteAccountDto>,
+) -> Result<StatusCode, AppError> {
+    if !state
+        .user_service
+        .validate_password_of_user_id(token.id, password)
+        .await
+    {
+        return Err(auth::AuthError::InvalidCredentials.into());
+    }
+
+    state
+        .user_service
+        .delete_account(token.id, state.config.deleted_posts_policy)
+        .await
+        .ok_or(AppError::InternalServerError)?;
+
+    state.auth_token_service.revoke_tokens_for(token.id).await;
+    state
+        .refresh_token_service
+        .revoke_tokens_for(token.id)
+        .await;
+
+    Ok(StatusCode::NO_CONTENT)
 }
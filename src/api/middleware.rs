@@ -0,0 +1,218 @@
+use crate::{
+    AppState,
+    extract::{Auth, ClientIp},
+    response::AppError,
+    service::RateLimit,
+    state::RouteRateLimit,
+};
+use axum::{
+    Router,
+    extract::{FromRequestParts, OptionalFromRequestParts, Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::{Next, from_fn_with_state},
+    response::{IntoResponse, Response},
+};
+
+/// Route group a rate limit layer is applied to
+#[derive(Clone)]
+struct RateLimitScope {
+    state: AppState,
+    group: &'static str,
+    budget: RouteRateLimit,
+}
+
+/// Applies request budgets to every route of the router.
+///
+/// Authenticated callers are counted per user, anonymous callers (and
+/// callers with invalid tokens) per client IP. Counters are kept in Redis so
+/// budgets are shared across API instances. Every response carries
+/// `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset`
+/// headers, requests exceeding the budget are rejected with
+/// [`AppError::TooManyRequests`]. The caller's token is validated once, and
+/// kept for [`Auth`] extractors of the handler.
+///
+/// Requests are let through without the headers if Redis is unavailable, so
+/// an outage of the rate limiter does not take the API down with it.
+pub fn rate_limited(
+    router: Router,
+    state: &AppState,
+    group: &'static str,
+    budget: RouteRateLimit,
+) -> Router {
+    router.layer(from_fn_with_state(
+        RateLimitScope {
+            state: state.clone(),
+            group,
+            budget,
+        },
+        rate_limit,
+    ))
+}
+
+async fn rate_limit(State(scope): State<RateLimitScope>, request: Request, next: Next) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let state = &scope.state;
+
+    let auth = <Auth as OptionalFromRequestParts<_>>::from_request_parts(&mut parts, state)
+        .await
+        .ok()
+        .flatten();
+
+    let (key, id, rule) = match auth {
+        Some(Auth(token)) => (
+            format!("{}-user", scope.group),
+            token.id.to_string(),
+            scope.budget.authenticated,
+        ),
+        None => {
+            let Ok(ClientIp(ip)) = ClientIp::from_request_parts(&mut parts, state).await;
+
+            (
+                format!("{}-ip", scope.group),
+                ip.to_string(),
+                scope.budget.anonymous,
+            )
+        }
+    };
+
+    let Some(limit) = state.rate_limit_service.hit(&key, &id, rule).await else {
+        // fails open, see `rate_limited`
+        return next.run(Request::from_parts(parts, body)).await;
+    };
+
+    let mut response = match limit.retry_after {
+        Some(seconds) => AppError::TooManyRequests(seconds).into_response(),
+        None => next.run(Request::from_parts(parts, body)).await,
+    };
+
+    insert_headers(response.headers_mut(), &limit);
+
+    response
+}
+
+fn insert_headers(headers: &mut HeaderMap, limit: &RateLimit) {
+    headers.insert("x-ratelimit-limit", HeaderValue::from(limit.limit));
+    headers.insert("x-ratelimit-remaining", HeaderValue::from(limit.remaining));
+    headers.insert("x-ratelimit-reset", HeaderValue::from(limit.reset));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        extract::ValidatedToken,
+        service::token_service::AuthToken,
+        snowflake,
+        state::RateLimitRule,
+        testutil::{test_redis, test_state},
+    };
+    use axum::{
+        body::{Body, to_bytes},
+        http::{StatusCode, header::AUTHORIZATION},
+        routing::get,
+    };
+    use redis::AsyncCommands;
+    use serial_test::serial;
+    use tower::ServiceExt;
+
+    #[serial]
+    #[tokio::test]
+    async fn route_budgets() {
+        let state = test_state().await;
+        let group = format!("test-{}", snowflake()).leak();
+        let router = rate_limited(
+            Router::new().route(
+                "/",
+                get(|request: Request| async move {
+                    // whether the token validated by the layer is kept
+                    request
+                        .extensions()
+                        .get::<ValidatedToken>()
+                        .is_some()
+                        .to_string()
+                }),
+            ),
+            &state,
+            group,
+            RouteRateLimit::new(RateLimitRule::new(2, 60), RateLimitRule::new(3, 60)),
+        );
+        let jwt = state
+            .auth_token_service
+            .sign(AuthToken::new(1001, String::from("user01")))
+            .await;
+
+        let send = |authorization: Option<String>| {
+            let mut request = Request::builder().uri("/");
+            if let Some(authorization) = authorization {
+                request = request.header(AUTHORIZATION, authorization);
+            }
+
+            router.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        let body = |response: Response| async {
+            to_bytes(response.into_body(), usize::MAX).await.unwrap()
+        };
+        let header = |response: &Response, name| {
+            response.headers()[name]
+                .to_str()
+                .unwrap()
+                .parse::<u64>()
+                .unwrap()
+        };
+
+        for remaining in (0..2).rev() {
+            let response = send(None).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(header(&response, "x-ratelimit-limit"), 2);
+            assert_eq!(header(&response, "x-ratelimit-remaining"), remaining);
+            assert!((1..=60).contains(&header(&response, "x-ratelimit-reset")));
+            assert_eq!(body(response).await, "false");
+        }
+
+        let response = send(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&response, "x-ratelimit-remaining"), 0);
+
+        // invalid tokens are counted with anonymous callers
+        let response = send(Some(String::from("Bearer x"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        for remaining in (0..3).rev() {
+            let response = send(Some(format!("Bearer {jwt}"))).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(header(&response, "x-ratelimit-limit"), 3);
+            assert_eq!(header(&response, "x-ratelimit-remaining"), remaining);
+            assert_eq!(body(response).await, "true");
+        }
+
+        let response = send(Some(format!("Bearer {jwt}"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn fails_open() {
+        let state = test_state().await;
+        let group = format!("test-{}", snowflake()).leak();
+        let router = rate_limited(
+            Router::new().route("/", get(|| async {})),
+            &state,
+            group,
+            RouteRateLimit::new(RateLimitRule::new(1, 60), RateLimitRule::new(1, 60)),
+        );
+
+        // Counter commands fail on a key holding a string.
+        let key = format!("rate-limit:{group}-ip:0.0.0.0");
+        let mut redis = test_redis().await.client();
+        redis.set::<_, _, ()>(&key, "x").await.unwrap();
+
+        for _ in 0..2 {
+            let request = Request::builder().uri("/").body(Body::empty()).unwrap();
+            let response = router.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(!response.headers().contains_key("x-ratelimit-limit"));
+        }
+
+        redis.del::<_, ()>(&key).await.unwrap();
+    }
+}
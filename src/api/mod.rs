@@ -3,3 +3,9 @@ pub mod handlers;
 
 /// API routes
 pub mod routes;
+
+/// Request extractors
+pub mod extract;
+
+/// Request middlewares
+pub mod middleware;
@@ -0,0 +1,21 @@
+use crate::{AppState, entity::UserFlag, handlers::admin_handler as admin};
+use axum::{Router, routing::put};
+use utoipa::OpenApi;
+
+/// Admin API documentations
+#[derive(OpenApi)]
+#[openapi(
+    paths(admin::grant_flag, admin::revoke_flag),
+    components(schemas(UserFlag))
+)]
+pub struct AdminApiDoc;
+
+/// Admin routes
+pub fn admin_routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/users/{id}/flags/{flag}",
+            put(admin::grant_flag).delete(admin::revoke_flag),
+        )
+        .with_state(state)
+}
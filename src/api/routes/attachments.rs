@@ -0,0 +1,33 @@
+use crate::{
+    AppState,
+    dto::attachments::{AttachmentDto, AttachmentError, UploadAttachmentDto},
+    entity::AttachmentVariant,
+    handlers::attachment_handler as attachments,
+};
+use axum::{
+    Router,
+    extract::DefaultBodyLimit,
+    routing::{get, post},
+};
+use utoipa::OpenApi;
+
+/// Attachments API documentations
+#[derive(OpenApi)]
+#[openapi(
+    paths(attachments::upload_attachment, attachments::get_attachment),
+    components(schemas(AttachmentDto, UploadAttachmentDto, AttachmentVariant, AttachmentError))
+)]
+pub struct AttachmentsApiDoc;
+
+/// Attachments routes
+pub fn attachment_routes(state: AppState) -> Router {
+    // Leaves room for multipart boundaries and headers, size of the file
+    // itself is checked by the upload handler.
+    let body_limit = state.config.attachment_max_size as usize + 64 * 1024;
+
+    Router::new()
+        .route("/", post(attachments::upload_attachment))
+        .route("/{id}", get(attachments::get_attachment))
+        .layer(DefaultBodyLimit::max(body_limit))
+        .with_state(state)
+}
@@ -1,6 +1,9 @@
 use crate::{
     AppState,
-    dto::auth::{AuthError, AuthUserDto, TokenDto},
+    dto::auth::{
+        AuthError, AuthUserDto, ChangePasswordDto, PasswordResetConfirmDto,
+        PasswordResetRequestDto, RefreshTokenDto, TokenDto,
+    },
     handlers::auth_handler as auth,
 };
 use axum::{Router, routing::post};
@@ -9,8 +12,25 @@ use utoipa::OpenApi;
 /// Authentication API documentations
 #[derive(OpenApi)]
 #[openapi(
-    paths(auth::register, auth::login),
-    components(schemas(AuthUserDto, TokenDto, AuthError))
+    paths(
+        auth::register,
+        auth::login,
+        auth::logout,
+        auth::logout_all,
+        auth::change_password,
+        auth::refresh,
+        auth::request_password_reset,
+        auth::confirm_password_reset,
+    ),
+    components(schemas(
+        AuthUserDto,
+        ChangePasswordDto,
+        RefreshTokenDto,
+        PasswordResetRequestDto,
+        PasswordResetConfirmDto,
+        TokenDto,
+        AuthError
+    ))
 )]
 pub struct AuthApiDoc;
 
@@ -19,5 +39,17 @@ pub fn auth_routes(state: AppState) -> Router {
     Router::new()
         .route("/register", post(auth::register))
         .route("/login", post(auth::login))
+        .route("/logout", post(auth::logout))
+        .route("/logout-all", post(auth::logout_all))
+        .route("/password", post(auth::change_password))
+        .route("/refresh", post(auth::refresh))
+        .route(
+            "/password-reset/request",
+            post(auth::request_password_reset),
+        )
+        .route(
+            "/password-reset/confirm",
+            post(auth::confirm_password_reset),
+        )
         .with_state(state)
 }
@@ -0,0 +1,34 @@
+use crate::{
+    AppState,
+    dto::emails::{AddEmailDto, EmailDto, EmailError, VerifyEmailDto},
+    handlers::email_handler as emails,
+};
+use axum::{
+    Router,
+    routing::{delete, get, post},
+};
+use utoipa::OpenApi;
+
+/// Emails API documentations
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        emails::get_emails,
+        emails::add_email,
+        emails::verify_email,
+        emails::resend_verification,
+        emails::delete_email,
+    ),
+    components(schemas(AddEmailDto, VerifyEmailDto, EmailDto, EmailError))
+)]
+pub struct EmailsApiDoc;
+
+/// Emails routes
+pub fn email_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(emails::get_emails).post(emails::add_email))
+        .route("/verify", post(emails::verify_email))
+        .route("/{id}", delete(emails::delete_email))
+        .route("/{id}/verification", post(emails::resend_verification))
+        .with_state(state)
+}
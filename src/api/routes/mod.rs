@@ -1,12 +1,27 @@
 mod auth;
 
+mod emails;
+
 mod posts;
 
 mod users;
 
 mod threads;
 
+mod timeline;
+
+mod admin;
+
+mod moderation;
+
+mod attachments;
+
+pub use admin::*;
+pub use attachments::*;
 pub use auth::*;
+pub use emails::*;
+pub use moderation::*;
 pub use posts::*;
 pub use threads::*;
+pub use timeline::*;
 pub use users::*;
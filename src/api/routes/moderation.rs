@@ -0,0 +1,62 @@
+use crate::{
+    AppState,
+    dto::{
+        moderation::{AuditEntryDto, ModerationError, ModerationReasonDto, SuspendUserDto},
+        report::{ReportDto, ReportGroupDto},
+    },
+    entity::{ReportReason, ReportTarget},
+    handlers::mod_handler as moderation,
+};
+use axum::{
+    Router,
+    routing::{delete, get, post, put},
+};
+use utoipa::OpenApi;
+
+/// Moderation API documentations
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        moderation::hide_post,
+        moderation::unhide_post,
+        moderation::remove_post,
+        moderation::suspend_user,
+        moderation::unsuspend_user,
+        moderation::get_audit_log,
+        moderation::get_report_queue,
+        moderation::get_reports,
+        moderation::dismiss_reports,
+    ),
+    components(schemas(
+        ModerationReasonDto,
+        SuspendUserDto,
+        AuditEntryDto,
+        ReportGroupDto,
+        ReportDto,
+        ReportTarget,
+        ReportReason,
+        ModerationError
+    ))
+)]
+pub struct ModApiDoc;
+
+/// Moderation routes
+pub fn mod_routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/posts/{id}/hide",
+            put(moderation::hide_post).delete(moderation::unhide_post),
+        )
+        .route("/posts/{id}", delete(moderation::remove_post))
+        .route(
+            "/users/{id}/suspend",
+            post(moderation::suspend_user).delete(moderation::unsuspend_user),
+        )
+        .route("/audit", get(moderation::get_audit_log))
+        .route("/reports", get(moderation::get_report_queue))
+        .route(
+            "/reports/{target_type}/{id}",
+            get(moderation::get_reports).delete(moderation::dismiss_reports),
+        )
+        .with_state(state)
+}
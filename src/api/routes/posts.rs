@@ -1,19 +1,66 @@
-use crate::{AppState, dto::posts::PostDto, handlers::post_handler as posts};
-use axum::{Router, routing::get};
+use crate::{
+    AppState,
+    dto::{
+        posts::{CreatePostDto, EditPostDto, PostContextDto, PostDto, PostError, ReplyTreeDto},
+        report::{CreateReportDto, ReportError},
+    },
+    entity::ReportReason,
+    handlers::post_handler as posts,
+};
+use axum::{
+    Router,
+    routing::{get, post, put},
+};
 use utoipa::OpenApi;
 
 /// Posts API documentations
 #[derive(OpenApi)]
 #[openapi(
-    paths(posts::get_post_by_id, posts::get_post_stats_by_id),
-    components(schemas(PostDto))
+    paths(
+        posts::get_post_by_id,
+        posts::get_post_stats_by_id,
+        posts::create_post,
+        posts::edit_post,
+        posts::delete_post,
+        posts::like_post,
+        posts::unlike_post,
+        posts::get_likes,
+        posts::get_replies,
+        posts::get_post_context,
+        posts::report_post,
+    ),
+    components(schemas(
+        PostDto,
+        ReplyTreeDto,
+        PostContextDto,
+        CreatePostDto,
+        EditPostDto,
+        CreateReportDto,
+        ReportReason,
+        PostError,
+        ReportError
+    ))
 )]
 pub struct PostsApiDoc;
 
 /// Posts routes
 pub fn post_routes(state: AppState) -> Router {
     Router::new()
-        .route("/{id}", get(posts::get_post_by_id))
+        .route("/", post(posts::create_post))
+        .route(
+            "/{id}",
+            get(posts::get_post_by_id)
+                .patch(posts::edit_post)
+                .delete(posts::delete_post),
+        )
         .route("/{id}/stats", get(posts::get_post_stats_by_id))
+        .route(
+            "/{id}/like",
+            put(posts::like_post).delete(posts::unlike_post),
+        )
+        .route("/{id}/likes", get(posts::get_likes))
+        .route("/{id}/replies", get(posts::get_replies))
+        .route("/{id}/context", get(posts::get_post_context))
+        .route("/{id}/report", post(posts::report_post))
         .with_state(state)
 }
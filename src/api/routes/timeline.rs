@@ -0,0 +1,15 @@
+use crate::{AppState, dto::posts::PostDto, handlers::timeline_handler as timeline};
+use axum::{Router, routing::get};
+use utoipa::OpenApi;
+
+/// Timeline API documentations
+#[derive(OpenApi)]
+#[openapi(paths(timeline::get_timeline), components(schemas(PostDto)))]
+pub struct TimelineApiDoc;
+
+/// Timeline routes
+pub fn timeline_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(timeline::get_timeline))
+        .with_state(state)
+}
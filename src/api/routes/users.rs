@@ -1,9 +1,19 @@
 use crate::{
     AppState,
-    dto::user::{FullProfileDto, UserDto},
+    dto::{
+        report::{CreateReportDto, ReportError},
+        user::{
+            ChangeUsernameDto, DeleteAccountDto, EditProfileDto, FullProfileDto, RelationshipDto,
+            UserDto, UserError,
+        },
+    },
+    entity::ReportReason,
     handlers::user_handler as users,
 };
-use axum::{Router, routing::get};
+use axum::{
+    Router,
+    routing::{get, post, put},
+};
 use utoipa::OpenApi;
 
 /// Users API documentations
@@ -17,14 +27,49 @@ use utoipa::OpenApi;
         users::get_user_stats_by_id,
         users::get_follows,
         users::get_followers,
+        users::follow_user,
+        users::unfollow_user,
+        users::block_user,
+        users::unblock_user,
+        users::mute_user,
+        users::unmute_user,
+        users::report_user,
+        users::get_me,
+        users::get_my_profile,
+        users::get_my_stats,
+        users::get_my_blocks,
+        users::get_my_mutes,
+        users::edit_profile,
+        users::change_username,
+        users::delete_account,
     ),
-    components(schemas(UserDto, FullProfileDto))
+    components(schemas(
+        UserDto,
+        FullProfileDto,
+        RelationshipDto,
+        EditProfileDto,
+        ChangeUsernameDto,
+        DeleteAccountDto,
+        CreateReportDto,
+        ReportReason,
+        UserError,
+        ReportError
+    ))
 )]
 pub struct UsersApiDoc;
 
 /// Users routes
 pub fn user_routes(state: AppState) -> Router {
     Router::new()
+        .route("/@me", get(users::get_me).delete(users::delete_account))
+        .route(
+            "/@me/profile",
+            get(users::get_my_profile).patch(users::edit_profile),
+        )
+        .route("/@me/stats", get(users::get_my_stats))
+        .route("/@me/username", post(users::change_username))
+        .route("/@me/blocks", get(users::get_my_blocks))
+        .route("/@me/mutes", get(users::get_my_mutes))
         .route("/{id}", get(users::get_user_by_id))
         .route("/@{username}", get(users::get_user_by_username))
         .route("/{id}/profile", get(users::get_profile_by_id))
@@ -32,5 +77,18 @@ pub fn user_routes(state: AppState) -> Router {
         .route("/{id}/stats", get(users::get_user_stats_by_id))
         .route("/{id}/follows", get(users::get_follows))
         .route("/{id}/followers", get(users::get_followers))
+        .route(
+            "/{id}/follow",
+            put(users::follow_user).delete(users::unfollow_user),
+        )
+        .route(
+            "/{id}/block",
+            put(users::block_user).delete(users::unblock_user),
+        )
+        .route(
+            "/{id}/mute",
+            put(users::mute_user).delete(users::unmute_user),
+        )
+        .route("/{id}/report", post(users::report_user))
         .with_state(state)
 }
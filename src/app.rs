@@ -1,9 +1,12 @@
-use crate::{AppState, response::AppErrorDto, routes};
+use crate::{AppState, middleware::rate_limited, response::AppErrorDto, routes};
 use axum::{Json, Router, routing::get};
 use lazy_static::lazy_static;
 use serde::Serialize;
 use std::time::Instant;
-use utoipa::{OpenApi, ToSchema};
+use utoipa::{
+    Modify, OpenApi, ToSchema,
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
 
 /// Application status
 #[derive(Serialize, ToSchema)]
@@ -23,22 +26,52 @@ struct ApiStatus {
     tags(
         (name = "default", description = "Miscellaneous uncategorized API endpoints"),
         (name = "auth", description = "User authentication endpoints"),
+        (name = "emails", description = "Account email API"),
         (name = "posts", description = "Post API"),
         (name = "users", description = "User API"),
         (name = "threads", description = "Thread API"),
+        (name = "timeline", description = "Home timeline API"),
+        (name = "admin", description = "Administration API"),
+        (name = "mod", description = "Moderation API"),
+        (name = "attachments", description = "Attachment API"),
     ),
     nest(
         (path = "/auth", api = routes::AuthApiDoc),
+        (path = "/emails", api = routes::EmailsApiDoc),
         (path = "/posts", api = routes::PostsApiDoc),
         (path = "/users", api = routes::UsersApiDoc),
         (path = "/threads", api = routes::ThreadsApiDoc),
+        (path = "/timeline", api = routes::TimelineApiDoc),
+        (path = "/admin", api = routes::AdminApiDoc),
+        (path = "/mod", api = routes::ModApiDoc),
+        (path = "/attachments", api = routes::AttachmentsApiDoc),
     ),
     servers(
         (url = "http://localhost:1186", description = "Default development server")
     ),
+    modifiers(&SecurityAddon),
 )]
 struct ApiDoc;
 
+/// Registers `bearer_auth` security scheme used by authenticated endpoints.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
 lazy_static! {
     static ref STARTUP_TIME: Instant = Instant::now();
 }
@@ -79,7 +112,52 @@ pub async fn create_router(state: AppState) -> Router {
         .route("/openapi.json", get(openapi))
         .with_state(state.clone())
         .nest("/auth", routes::auth_routes(state.clone()))
-        .nest("/users", routes::user_routes(state.clone()))
-        .nest("/posts", routes::post_routes(state.clone()))
-        .nest("/threads", routes::thread_routes(state.clone()))
+        .nest("/emails", routes::email_routes(state.clone()))
+        .nest(
+            "/users",
+            rate_limited(
+                routes::user_routes(state.clone()),
+                &state,
+                "users",
+                state.config.users_rate_limit,
+            ),
+        )
+        .nest(
+            "/posts",
+            rate_limited(
+                routes::post_routes(state.clone()),
+                &state,
+                "posts",
+                state.config.posts_rate_limit,
+            ),
+        )
+        .nest(
+            "/threads",
+            rate_limited(
+                routes::thread_routes(state.clone()),
+                &state,
+                "threads",
+                state.config.threads_rate_limit,
+            ),
+        )
+        .nest(
+            "/timeline",
+            rate_limited(
+                routes::timeline_routes(state.clone()),
+                &state,
+                "timeline",
+                state.config.timeline_rate_limit,
+            ),
+        )
+        .nest("/admin", routes::admin_routes(state.clone()))
+        .nest("/mod", routes::mod_routes(state.clone()))
+        .nest(
+            "/attachments",
+            rate_limited(
+                routes::attachment_routes(state.clone()),
+                &state,
+                "attachments",
+                state.config.attachments_rate_limit,
+            ),
+        )
 }
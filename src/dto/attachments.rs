@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use serde_with::{DisplayFromStr, serde_as};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::entity::{self, AttachmentVariant};
+
+api_errors!(
+    AttachmentError,
+    responses(
+        AttachmentNotFound = (
+            status = NOT_FOUND,
+            description = "Could not find the attachment.",
+            variants = (
+                AttachmentNotFound = "Attachment not found.",
+                VariantNotFound = "Attachment does not have the requested variant.",
+            )
+        ),
+        InappropriateAttachment = (
+            status = BAD_REQUEST,
+            description = "Could not upload the file.",
+            variants = (
+                MissingFile = "Request does not contain a `file` field.",
+                UnreadableFile = "Could not read the uploaded file.",
+                EmptyFile = "File cannot be empty.",
+                UnsupportedType = "File type is not allowed.",
+                MalformedImage = "Could not decode the image.",
+                UnsupportedAnimation =
+                    "Animated WebP images are not supported, upload a GIF instead.",
+            )
+        ),
+        AttachmentTooLarge = (
+            status = PAYLOAD_TOO_LARGE,
+            description = "The file exceeds the upload size limit.",
+            variants = (
+                FileTooLarge((u64)) = "File cannot be larger than {0} bytes."((8388608)),
+                ImageTooLarge((u64)) = "Image cannot have more than {0} pixels."((40000000)),
+                TooManyFrames((u64)) = "Animated image cannot have more than {0} frames."((256)),
+            )
+        ),
+    )
+);
+
+/// Attachment data transfer object
+#[serde_as]
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct AttachmentDto {
+    /// Unique identifier for the attachment
+    #[schema(value_type = String)]
+    #[serde_as(as = "DisplayFromStr")]
+    pub id: i64,
+    /// The user that uploaded the attachment
+    #[schema(value_type = String)]
+    #[serde_as(as = "DisplayFromStr")]
+    pub user_id: i64,
+    /// MIME type of the file
+    pub mime_type: String,
+    /// File size, in bytes
+    pub size: u64,
+    /// Hex-encoded SHA-256 checksum of the file
+    pub checksum: String,
+}
+
+impl From<entity::Attachment> for AttachmentDto {
+    fn from(attachment: entity::Attachment) -> Self {
+        AttachmentDto {
+            id: attachment.id,
+            user_id: attachment.user_id,
+            mime_type: attachment.mime_type,
+            size: attachment.size as u64,
+            checksum: attachment.checksum,
+        }
+    }
+}
+
+/// Multipart form of a file upload
+#[derive(ToSchema)]
+pub struct UploadAttachmentDto {
+    /// File content
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+/// Attachment query params.
+#[derive(Deserialize, IntoParams)]
+pub struct AttachmentQuery {
+    /// Resized variant of an image attachment, the original file is returned
+    /// if not set
+    pub variant: Option<AttachmentVariant>,
+}
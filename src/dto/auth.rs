@@ -21,7 +21,7 @@ api_errors!(
                 UsernameTaken((String)) = "Username {0} has been taken."((String::from("example"))),
                 UsernameTooLong = "Username cannot contain more than 20 characters.",
                 UsernameRejected = "Username contains inappropriate characters.",
-                PasswordRejected = "Invalid password.",
+                PasswordRejected = "Password does not meet the length requirements.",
             )
         ),
         InvalidCredentials = (
@@ -29,6 +29,35 @@ api_errors!(
             description = "The username or password you entered is incorrect.",
             variants = (InvalidCredentials = "Provided username or password is incorrect",)
         ),
+        Unauthorized = (
+            status = UNAUTHORIZED,
+            description = "The request requires a valid authentication token.",
+            variants = (
+                MissingToken = "Authentication token is missing.",
+                InvalidToken = "Authentication token is invalid or has expired.",
+                InvalidRefreshToken = "Refresh token is invalid, expired or already used.",
+            )
+        ),
+        PasswordResetFailed = (
+            status = BAD_REQUEST,
+            description = "Could not reset the password.",
+            variants = (InvalidResetToken = "Password reset token is invalid or has expired.",)
+        ),
+        Forbidden = (
+            status = FORBIDDEN,
+            description = "The caller is not allowed to perform the action.",
+            variants = (
+                MissingPermission = "You do not have permission to perform this action.",
+                AccountBanned = "This account has been banned.",
+                AccountSuspended((u64)) = "This account is suspended for {0} more seconds."((3600)),
+            )
+        ),
+        TooManyAttempts = (
+            status = TOO_MANY_REQUESTS,
+            description = "Too many login, registration or mail requests.",
+            variants =
+                (TooManyAttempts((u64)) = "Too many attempts, try again in {0} seconds."((60)),)
+        ),
     )
 );
 
@@ -39,6 +68,24 @@ lazy_static!(
             .unwrap();
 );
 
+/// Validates the username against registration rules, returning it in
+/// lowercase.
+pub fn validate_username(username: &str) -> Result<String, AuthError> {
+    match username.len() {
+        3..=20 => (),
+        v if v < 3 => return Err(AuthError::UsernameTooShort),
+        _ => return Err(AuthError::UsernameTooLong),
+    };
+
+    let username = username.to_lowercase();
+
+    if USERNAME_REGEX.is_match(&username) {
+        Ok(username)
+    } else {
+        Err(AuthError::UsernameRejected)
+    }
+}
+
 /// Username and password for account creation or login
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct AuthUserDto {
@@ -50,11 +97,45 @@ pub struct AuthUserDto {
     pub password: String,
 }
 
+/// Current and new password for password change
+#[derive(Deserialize, ToSchema)]
+pub struct ChangePasswordDto {
+    /// Current password
+    pub current_password: String,
+    /// New password
+    pub new_password: String,
+}
+
+/// Email to send password reset token to
+#[derive(Deserialize, ToSchema)]
+pub struct PasswordResetRequestDto {
+    /// Verified email of the account
+    pub email: String,
+}
+
+/// Password reset token and the new password
+#[derive(Deserialize, ToSchema)]
+pub struct PasswordResetConfirmDto {
+    /// Token received in the password reset mail
+    pub token: String,
+    /// New password
+    pub new_password: String,
+}
+
+/// Refresh token to be rotated or revoked
+#[derive(Deserialize, ToSchema)]
+pub struct RefreshTokenDto {
+    /// Refresh token
+    pub refresh_token: String,
+}
+
 /// Account token with full profile
 #[derive(Serialize, ToSchema)]
 pub struct TokenDto {
-    /// JSON Web Token
+    /// Short-lived JSON Web Token for authenticating requests
     pub token: String,
+    /// Long-lived, single-use token for obtaining a new token pair
+    pub refresh_token: String,
     /// User's profile
     pub user: FullProfileDto,
 }
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use serde_with::{DisplayFromStr, serde_as};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::entity;
+
+api_errors!(
+    EmailError,
+    responses(
+        EmailNotFound = (
+            status = NOT_FOUND,
+            description = "Could not find the email.",
+            variants = (EmailNotFound = "Email not found.")
+        ),
+        InappropriateEmail = (
+            status = BAD_REQUEST,
+            description = "Could not add the email to the account.",
+            variants = (
+                InvalidEmail = "Invalid email address.",
+                TooManyEmails = "Account cannot have more than 5 emails.",
+            )
+        ),
+        EmailTaken = (
+            status = CONFLICT,
+            description = "The email is already on the account.",
+            variants = (
+                EmailTaken = "Email has already been added to your account.",
+                AlreadyVerified = "Email has already been verified.",
+            )
+        ),
+        VerificationFailed = (
+            status = BAD_REQUEST,
+            description = "Could not verify the email.",
+            variants =
+                (InvalidVerificationToken = "Verification token is invalid or has expired.",)
+        ),
+    )
+);
+
+/// Maximum email count of an account
+pub const MAX_EMAILS_PER_USER: i64 = 5;
+
+/// Email address to add to the account
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct AddEmailDto {
+    /// Email address
+    #[validate(email, length(max = 254))]
+    pub email: String,
+}
+
+/// Token received in the verification mail
+#[derive(Deserialize, ToSchema)]
+pub struct VerifyEmailDto {
+    /// Verification token
+    pub token: String,
+}
+
+/// Email data transfer object
+#[serde_as]
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct EmailDto {
+    /// Unique identifier for email
+    #[schema(value_type = String)]
+    #[serde_as(as = "DisplayFromStr")]
+    pub id: i64,
+    /// Email
+    pub email: String,
+    /// Whether or not the email has been verified
+    pub is_verified: bool,
+}
+
+impl From<entity::Email> for EmailDto {
+    fn from(email: entity::Email) -> Self {
+        EmailDto {
+            id: email.id,
+            email: email.email,
+            is_verified: email.is_verified,
+        }
+    }
+}
@@ -4,12 +4,24 @@ use utoipa::IntoParams;
 #[macro_use]
 mod macros;
 
+/// Attachment DTOs
+pub mod attachments;
+
 /// Auth DTOs
 pub mod auth;
 
+/// Email DTOs
+pub mod emails;
+
+/// Moderation DTOs
+pub mod moderation;
+
 /// Post DTOs
 pub mod posts;
 
+/// Report DTOs
+pub mod report;
+
 /// User DTOs
 pub mod user;
 
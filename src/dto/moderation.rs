@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use serde_with::{DisplayFromStr, serde_as};
+use utoipa::ToSchema;
+
+use crate::entity;
+
+api_errors!(
+    ModerationError,
+    responses(
+        InappropriateModeration = (
+            status = BAD_REQUEST,
+            description = "Could not perform the moderation action.",
+            variants = (
+                ReasonEmpty = "Reason cannot be empty.",
+                ReasonTooLong = "Reason cannot contain more than 512 characters.",
+                InvalidDuration = "Suspension duration must be between 1 second and 10 years.",
+            )
+        ),
+        CannotModerate = (
+            status = FORBIDDEN,
+            description = "The caller cannot moderate the target.",
+            variants = (CannotModerateUser = "Moderators can only be moderated by admins.",)
+        ),
+    )
+);
+
+/// Maximum character count of moderation reasons
+pub const MAX_REASON_LENGTH: usize = 512;
+
+/// Maximum suspension duration, 10 years in seconds
+pub const MAX_SUSPENSION_DURATION: u64 = 10 * 365 * 24 * 3600;
+
+/// Validates the reason of a moderation action.
+pub fn validate_reason(reason: &str) -> Result<(), ModerationError> {
+    if reason.trim().is_empty() {
+        Err(ModerationError::ReasonEmpty)
+    } else if reason.chars().count() > MAX_REASON_LENGTH {
+        Err(ModerationError::ReasonTooLong)
+    } else {
+        Ok(())
+    }
+}
+
+/// Reason of a moderation action
+#[derive(Deserialize, ToSchema)]
+pub struct ModerationReasonDto {
+    /// Reason, recorded in the audit log
+    pub reason: String,
+}
+
+/// Suspension of an user
+#[derive(Deserialize, ToSchema)]
+pub struct SuspendUserDto {
+    /// Suspension duration, in seconds
+    pub duration: u64,
+    /// Reason, recorded in the audit log
+    pub reason: String,
+}
+
+/// Audit log entry
+#[serde_as]
+#[derive(Serialize, ToSchema)]
+pub struct AuditEntryDto {
+    /// Entry ID
+    #[schema(value_type = String)]
+    #[serde_as(as = "DisplayFromStr")]
+    pub id: i64,
+    /// ID of the moderator or admin that performed the action
+    #[schema(value_type = String)]
+    #[serde_as(as = "DisplayFromStr")]
+    pub actor_id: i64,
+    /// Action name, such as `hide_post` or `suspend_user`
+    pub action: String,
+    /// ID of the post or user the action was performed on
+    #[schema(value_type = String)]
+    #[serde_as(as = "DisplayFromStr")]
+    pub target_id: i64,
+    /// Reason or other action specific details
+    pub details: Option<String>,
+}
+
+impl From<entity::AuditEntry> for AuditEntryDto {
+    fn from(entry: entity::AuditEntry) -> Self {
+        AuditEntryDto {
+            id: entry.id,
+            actor_id: entry.actor_id,
+            action: entry.action,
+            target_id: entry.target_id,
+            details: entry.details,
+        }
+    }
+}
@@ -1,7 +1,7 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_with::{DisplayFromStr, serde_as};
 use sqlx::prelude::FromRow;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::entity;
 
@@ -13,9 +13,35 @@ api_errors!(
             description = "Could not find the post.",
             variants = (PostNotFound = "Post not found.")
         ),
+        ThreadNotFound = (
+            status = NOT_FOUND,
+            description = "Could not find the thread that the post is sent to.",
+            variants = (ThreadNotFound = "Thread not found.")
+        ),
+        NotPostAuthor = (
+            status = FORBIDDEN,
+            description = "Only the author can modify the post.",
+            variants = (NotPostAuthor = "You are not the author of this post.")
+        ),
+        InappropriatePost = (
+            status = BAD_REQUEST,
+            description = "Could not send a post with provided content.",
+            variants = (
+                ContentEmpty = "Post content cannot be empty.",
+                ContentTooLong = "Post content cannot contain more than 2048 characters.",
+                TooManyAttachments = "Post cannot contain more than 4 attachments.",
+                AttachmentNotOwned = "Attachment does not exist or does not belong to you.",
+            )
+        ),
     )
 );
 
+/// Maximum character count of a post's content
+pub const MAX_POST_LENGTH: usize = 2048;
+
+/// Maximum attachment count of a post
+pub const MAX_POST_ATTACHMENTS: usize = 4;
+
 /// Post data transfer object
 #[serde_as]
 #[derive(Clone, Debug, Serialize, ToSchema)]
@@ -40,12 +66,96 @@ pub struct PostDto {
     pub is_edited: bool,
     /// Content
     pub content: String,
+    /// List of attachment ids, `?variant=thumb` serves previews of images
+    #[schema(value_type = Vec<String>)]
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    pub attachments: Vec<i64>,
+}
+
+/// Reply with its own replies
+#[serde_as]
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ReplyTreeDto {
+    /// The reply
+    #[serde(flatten)]
+    pub post: PostDto,
+    /// Latest replies to the reply
+    #[schema(no_recursion)]
+    pub replies: Vec<ReplyTreeDto>,
+    /// Whether there are more replies than the ones included
+    pub has_more_replies: bool,
+    /// Continuation cursor for the rest of the replies, to be used as
+    /// `before` of `/posts/{id}/replies`. Not set if there are no more
+    /// replies, or none of them are included.
+    #[schema(value_type = Option<String>)]
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub next_before: Option<i64>,
+}
+
+/// Chain of posts that a reply is replying to
+#[serde_as]
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct PostContextDto {
+    /// Ancestors of the reply, root first, excluding the hidden ones
+    pub ancestors: Vec<PostDto>,
+    /// The thread that the root post belongs to, not set for main thread or
+    /// if ancestors are truncated
+    #[schema(value_type = Option<String>)]
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub root_thread_id: Option<i64>,
+    /// The user whose profile wall is the root thread, if it is a wall
+    #[schema(value_type = Option<String>)]
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub wall_user_id: Option<i64>,
+    /// Whether or not ancestors above the closest 256 are left out, the root
+    /// is unknown if so
+    pub truncated: bool,
+}
+
+/// Reply tree query params.
+#[derive(Deserialize, IntoParams)]
+pub struct ReplyTreeQuery {
+    /// Levels of replies to include, 2 if omitted, at most 3
+    pub depth: Option<u64>,
+    /// Limit of direct replies, replies below are limited to 4 per post
+    pub limit: Option<u64>,
+    /// Direct replies before that id
+    pub before: Option<i64>,
+}
+
+/// Content of a new post
+#[serde_as]
+#[derive(Deserialize, ToSchema)]
+pub struct CreatePostDto {
+    /// The thread that the post is sent to, main thread if omitted
+    #[schema(value_type = Option<String>)]
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub thread_id: Option<i64>,
+    /// Content
+    pub content: String,
     /// List of attachment ids
     #[schema(value_type = Vec<String>)]
     #[serde_as(as = "Vec<DisplayFromStr>")]
+    #[serde(default)]
     pub attachments: Vec<i64>,
 }
 
+/// Edited content of a post
+///
+/// Omitted fields are left unchanged.
+#[serde_as]
+#[derive(Deserialize, ToSchema)]
+pub struct EditPostDto {
+    /// Content
+    pub content: Option<String>,
+    /// List of attachment ids
+    #[schema(value_type = Option<Vec<String>>)]
+    #[serde_as(as = "Option<Vec<DisplayFromStr>>")]
+    #[serde(default)]
+    pub attachments: Option<Vec<i64>>,
+}
+
 /// Stats for user profile
 #[derive(Debug, FromRow, Serialize, ToSchema)]
 pub struct PostStatsDto {
@@ -55,16 +165,31 @@ pub struct PostStatsDto {
     pub likes: i64,
 }
 
-impl Into<PostDto> for entity::Post {
-    fn into(self) -> PostDto {
+impl From<entity::Post> for PostDto {
+    fn from(post: entity::Post) -> Self {
         PostDto {
-            id: self.id,
-            user_id: self.user_id,
-            thread_id: self.thread_id,
-            replies_thread_id: self.replies_thread_id,
-            is_edited: self.is_edited,
-            content: self.content,
-            attachments: self.attachments,
+            id: post.id,
+            user_id: post.user_id,
+            thread_id: post.thread_id,
+            replies_thread_id: post.replies_thread_id,
+            is_edited: post.is_edited,
+            content: post.content,
+            attachments: post.attachments,
         }
     }
 }
+
+/// Checks post content and attachment count against post limits.
+pub fn validate_post(content: &str, attachments: &[i64]) -> Result<(), PostError> {
+    match content.trim().chars().count() {
+        0 => return Err(PostError::ContentEmpty),
+        v if v > MAX_POST_LENGTH => return Err(PostError::ContentTooLong),
+        _ => (),
+    }
+
+    if attachments.len() > MAX_POST_ATTACHMENTS {
+        return Err(PostError::TooManyAttachments);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use serde_with::{DisplayFromStr, serde_as};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::entity::{self, ReportReason, ReportTarget};
+
+api_errors!(
+    ReportError,
+    responses(
+        InappropriateReport = (
+            status = BAD_REQUEST,
+            description = "Could not submit the report.",
+            variants = (
+                DetailsTooLong = "Report details cannot contain more than 512 characters.",
+                CannotReportSelf = "You cannot report yourself or your own posts.",
+            )
+        ),
+        AlreadyReported = (
+            status = CONFLICT,
+            description = "The caller has already reported the target.",
+            variants = (AlreadyReported = "You have already reported this.")
+        ),
+    )
+);
+
+/// Maximum character count of report details
+pub const MAX_REPORT_DETAILS_LENGTH: usize = 512;
+
+/// Validates the details of a report, blank details are dropped.
+pub fn validate_details(details: Option<String>) -> Result<Option<String>, ReportError> {
+    match details {
+        Some(details) if details.chars().count() > MAX_REPORT_DETAILS_LENGTH => {
+            Err(ReportError::DetailsTooLong)
+        }
+        Some(details) if details.trim().is_empty() => Ok(None),
+        details => Ok(details),
+    }
+}
+
+/// Report submission
+#[derive(Deserialize, ToSchema)]
+pub struct CreateReportDto {
+    /// Reason of the report
+    pub reason: ReportReason,
+    /// Optional explanation, up to 512 characters
+    pub details: Option<String>,
+}
+
+/// Report of a post or profile
+#[serde_as]
+#[derive(Serialize, ToSchema)]
+pub struct ReportDto {
+    /// Unique identifier for the report
+    #[schema(value_type = String)]
+    #[serde_as(as = "DisplayFromStr")]
+    pub id: i64,
+    /// Id of the user that submitted the report
+    #[schema(value_type = String)]
+    #[serde_as(as = "DisplayFromStr")]
+    pub reporter_id: i64,
+    /// Reason of the report
+    pub reason: ReportReason,
+    /// Explanation by the reporter
+    pub details: Option<String>,
+}
+
+impl From<entity::Report> for ReportDto {
+    fn from(report: entity::Report) -> Self {
+        ReportDto {
+            id: report.id,
+            reporter_id: report.reporter_id,
+            reason: report.reason,
+            details: report.details,
+        }
+    }
+}
+
+/// Reports of the same target in the moderation queue
+#[serde_as]
+#[derive(Serialize, ToSchema)]
+pub struct ReportGroupDto {
+    /// Kind of the reported content
+    pub target_type: ReportTarget,
+    /// Id of the reported post or user
+    #[schema(value_type = String)]
+    #[serde_as(as = "DisplayFromStr")]
+    pub target_id: i64,
+    /// Number of users that reported the target
+    pub report_count: u64,
+    /// Distinct reasons given by reporters
+    pub reasons: Vec<ReportReason>,
+    /// Id of the latest report
+    #[schema(value_type = String)]
+    #[serde_as(as = "DisplayFromStr")]
+    pub last_report_id: i64,
+}
+
+impl From<entity::ReportGroup> for ReportGroupDto {
+    fn from(group: entity::ReportGroup) -> Self {
+        ReportGroupDto {
+            target_type: group.target_type,
+            target_id: group.target_id,
+            report_count: group.report_count as u64,
+            reasons: group.reasons,
+            last_report_id: group.last_report_id,
+        }
+    }
+}
+
+/// Report queue query params.
+#[derive(Deserialize, IntoParams)]
+pub struct ReportQueueQuery {
+    /// Only list reports of that kind
+    pub target_type: Option<ReportTarget>,
+    /// Limit of element count
+    pub limit: Option<u64>,
+    /// Report count of the last target of the previous page
+    pub before_count: Option<u64>,
+    /// Last report id of the last target of the previous page
+    pub before: Option<i64>,
+}
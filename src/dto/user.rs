@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_with::{DisplayFromStr, serde_as};
 use sqlx::{prelude::FromRow, types::BitVec};
 use utoipa::ToSchema;
@@ -11,9 +11,56 @@ api_errors!(
             description = "Could not find the user.",
             variants = (UserNotFound = "User not found.")
         ),
+        InappropriateFollow = (
+            status = BAD_REQUEST,
+            description = "Could not follow the user.",
+            variants = (CannotFollowSelf = "You cannot follow yourself.")
+        ),
+        InappropriateBlock = (
+            status = BAD_REQUEST,
+            description = "Could not block or mute the user.",
+            variants = (
+                CannotBlockSelf = "You cannot block yourself.",
+                CannotMuteSelf = "You cannot mute yourself.",
+            )
+        ),
+        BlockedByUser = (
+            status = FORBIDDEN,
+            description = "The user has blocked the caller.",
+            variants = (BlockedByUser = "You have been blocked by this user.")
+        ),
+        InappropriateProfile = (
+            status = BAD_REQUEST,
+            description = "Could not update the profile with provided fields.",
+            variants = (
+                BioTooLong = "Bio cannot contain more than 256 characters.",
+                AttachmentNotOwned = "Attachment does not exist or does not belong to you.",
+            )
+        ),
+        FollowConflict = (
+            status = CONFLICT,
+            description = "Follow relation is already in the requested state.",
+            variants = (
+                AlreadyFollowing = "You are already following this user.",
+                NotFollowing = "You are not following this user.",
+            )
+        ),
+        BlockConflict = (
+            status = CONFLICT,
+            description = "Block or mute relation is already in the requested state.",
+            variants = (
+                AlreadyBlocking = "You are already blocking this user.",
+                NotBlocking = "You are not blocking this user.",
+                AlreadyMuting = "You are already muting this user.",
+                NotMuting = "You are not muting this user.",
+            )
+        ),
     )
 );
 
+/// Maximum character count of profile bios
+pub const MAX_BIO_LENGTH: usize = 256;
+
 /// User's minimal profile including its id and username
 #[serde_as]
 #[derive(Debug, FromRow, Serialize, ToSchema)]
@@ -28,7 +75,7 @@ pub struct UserDto {
     #[schema(value_type = String)]
     #[serde(serialize_with = "crate::enc::bitvec_as_hex")]
     pub flags: BitVec,
-    /// A thread id for comments on user's wall
+    /// Attachment id for user's avatar, `?variant=avatar` serves it resized
     #[schema(value_type = Option<String>)]
     #[serde_as(as = "Option<DisplayFromStr>")]
     pub avatar_id: Option<i64>,
@@ -52,16 +99,66 @@ pub struct FullProfileDto {
     #[schema(value_type = String)]
     #[serde_as(as = "DisplayFromStr")]
     pub comments_thread_id: i64,
-    /// Attachment id for user's avatar
+    /// Attachment id for user's avatar, `?variant=avatar` serves it resized
     #[schema(value_type = Option<String>)]
     #[serde_as(as = "Option<DisplayFromStr>")]
     pub avatar_id: Option<i64>,
-    /// Attachment id for user's banner
+    /// Attachment id for user's banner, `?variant=banner` serves it resized
     #[schema(value_type = Option<String>)]
     #[serde_as(as = "Option<DisplayFromStr>")]
     pub banner_id: Option<i64>,
     /// Biography
     pub bio: String,
+    /// Relationship between the user and the caller, present only for
+    /// authenticated requests
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relationship: Option<RelationshipDto>,
+}
+
+/// Edited fields of the caller's profile
+///
+/// Omitted fields are left unchanged, `null` attachment ids remove the avatar
+/// or banner.
+#[derive(Deserialize, ToSchema)]
+pub struct EditProfileDto {
+    /// Biography
+    pub bio: Option<String>,
+    /// Attachment id for avatar
+    #[schema(value_type = Option<String>)]
+    #[serde(default, deserialize_with = "crate::enc::nullable_id")]
+    pub avatar_id: Option<Option<i64>>,
+    /// Attachment id for banner
+    #[schema(value_type = Option<String>)]
+    #[serde(default, deserialize_with = "crate::enc::nullable_id")]
+    pub banner_id: Option<Option<i64>>,
+}
+
+/// New username of the caller
+#[derive(Deserialize, ToSchema)]
+pub struct ChangeUsernameDto {
+    /// Username
+    pub username: String,
+}
+
+/// Password confirmation for account deletion
+#[derive(Deserialize, ToSchema)]
+pub struct DeleteAccountDto {
+    /// Current password
+    pub password: String,
+}
+
+/// Relationship between the caller and an user
+#[derive(Debug, Default, FromRow, Serialize, ToSchema)]
+pub struct RelationshipDto {
+    /// Whether or not the caller follows the user
+    pub is_following: bool,
+    /// Whether or not the user follows the caller
+    pub follows_you: bool,
+    /// Whether or not the caller blocks the user
+    pub is_blocking: bool,
+    /// Whether or not the caller mutes the user
+    pub is_muting: bool,
 }
 
 /// Stats for user profile
@@ -1,4 +1,4 @@
-use serde::Serializer;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
 use sqlx::types::BitVec;
 
 /// Serializes [`BitVec`] as hex string.
@@ -8,3 +8,19 @@ pub fn bitvec_as_hex<S: Serializer>(val: &BitVec, serializer: S) -> Result<S::Ok
 
     serializer.serialize_str(&hex_string)
 }
+
+/// Deserializes an optional, nullable ID sent as string.
+///
+/// Use with `#[serde(default)]` to tell omitted fields (outer [`None`]) apart
+/// from explicit `null`s (`Some(None)`).
+pub fn nullable_id<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<i64>>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(id) => id
+            .parse()
+            .map(|id| Some(Some(id)))
+            .map_err(D::Error::custom),
+        None => Ok(Some(None)),
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+/// Uploaded file that posts and profiles can reference
+#[derive(Clone, Debug, FromRow)]
+pub struct Attachment {
+    /// Unique identifier for the attachment
+    pub id: i64,
+    /// Id of the user that uploaded the attachment
+    pub user_id: i64,
+    /// MIME type of the file, detected from its content
+    pub mime_type: String,
+    /// File size, in bytes
+    pub size: i64,
+    /// Hex-encoded SHA-256 checksum of the file
+    pub checksum: String,
+}
+
+/// Resized copy of an image attachment, generated on upload
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentVariant {
+    /// 256x256 square crop, for avatars
+    Avatar,
+    /// 1500x500 crop, for profile banners
+    Banner,
+    /// Scaled to fit in 320x320, for post attachment previews
+    Thumb,
+}
+
+impl AttachmentVariant {
+    /// Every variant, in generation order.
+    pub const ALL: [AttachmentVariant; 3] = [
+        AttachmentVariant::Avatar,
+        AttachmentVariant::Banner,
+        AttachmentVariant::Thumb,
+    ];
+
+    /// Name of the variant as in API.
+    pub fn name(self) -> &'static str {
+        match self {
+            AttachmentVariant::Avatar => "avatar",
+            AttachmentVariant::Banner => "banner",
+            AttachmentVariant::Thumb => "thumb",
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use sqlx::prelude::FromRow;
+
+/// Record of an administrative action
+#[derive(Clone, Debug, FromRow)]
+pub struct AuditEntry {
+    /// Unique identifier for the entry
+    pub id: i64,
+    /// Id of the user that performed the action
+    pub actor_id: i64,
+    /// Action name, such as `grant_flag`
+    pub action: String,
+    /// Id of the user or post the action was performed on
+    pub target_id: i64,
+    /// Action specific details
+    pub details: Option<String>,
+}
@@ -1,6 +1,13 @@
+mod attachment;
+mod audit;
 mod post;
+mod report;
 mod user;
 
-pub use user::{Email, Profile, User};
+pub use attachment::{Attachment, AttachmentVariant};
+pub use audit::AuditEntry;
+pub use report::{Report, ReportGroup, ReportReason, ReportTarget};
 
-pub use post::{Post, Thread};
+pub use user::{Email, Profile, USER_FLAGS_LEN, User, UserFlag};
+
+pub use post::{Ancestor, Post, Reply, Thread};
@@ -19,6 +19,28 @@ pub struct Post {
     pub attachments: Vec<i64>,
 }
 
+/// Post fetched as a node of a reply tree
+#[derive(Clone, Debug, FromRow)]
+pub struct Reply {
+    /// The reply
+    #[sqlx(flatten)]
+    pub post: Post,
+    /// Level of the reply in the tree, 1 for direct replies
+    pub depth: i32,
+    /// Whether the reply has replies that are not included in the tree
+    pub has_more_replies: bool,
+}
+
+/// Post fetched as an ancestor of a reply
+#[derive(Clone, Debug, FromRow)]
+pub struct Ancestor {
+    /// The ancestor
+    #[sqlx(flatten)]
+    pub post: Post,
+    /// Whether or not the ancestor is hidden by a moderator
+    pub is_hidden: bool,
+}
+
 /// Thread that posts are sent to
 #[derive(Clone, Debug, FromRow)]
 pub struct Thread {
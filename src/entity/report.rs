@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+/// Kind of the reported content
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum ReportTarget {
+    /// Report of a post
+    Post,
+    /// Report of an user's profile
+    User,
+}
+
+/// Reason of a report
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum ReportReason {
+    /// Unsolicited or repetitive content
+    Spam,
+    /// Harassment or bullying
+    Harassment,
+    /// Hateful content against a group
+    HateSpeech,
+    /// Violent or threatening content
+    Violence,
+    /// Sexually explicit content
+    Sexual,
+    /// Impersonation of another person
+    Impersonation,
+    /// Any other reason, explained in details
+    Other,
+}
+
+/// Report of a post or profile submitted by an user
+#[derive(Clone, Debug, FromRow)]
+pub struct Report {
+    /// Unique identifier for the report
+    pub id: i64,
+    /// Id of the user that submitted the report
+    pub reporter_id: i64,
+    /// Kind of the reported content
+    pub target_type: ReportTarget,
+    /// Id of the reported post or user
+    pub target_id: i64,
+    /// Reason of the report
+    pub reason: ReportReason,
+    /// Optional explanation by the reporter
+    pub details: Option<String>,
+}
+
+/// Reports of the same target, aggregated for the moderation queue
+#[derive(Clone, Debug, FromRow)]
+pub struct ReportGroup {
+    /// Kind of the reported content
+    pub target_type: ReportTarget,
+    /// Id of the reported post or user
+    pub target_id: i64,
+    /// Number of distinct reporters
+    pub report_count: i64,
+    /// Distinct reasons given by reporters
+    pub reasons: Vec<ReportReason>,
+    /// Id of the latest report
+    pub last_report_id: i64,
+}
@@ -1,4 +1,45 @@
+use serde::{Deserialize, Serialize};
 use sqlx::{prelude::FromRow, types::BitVec};
+use utoipa::ToSchema;
+
+/// Bit length of [`User::flags`]
+pub const USER_FLAGS_LEN: usize = 8;
+
+/// Administrative user flag, each one is a bit of [`User::flags`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UserFlag {
+    /// Manages user flags, implies [`UserFlag::Moderator`]
+    Admin = 0,
+    /// Moderates posts and users
+    Moderator = 1,
+    /// Verified account
+    Verified = 2,
+    /// Banned account, cannot log in
+    Banned = 3,
+}
+
+impl UserFlag {
+    /// Index of the flag's bit.
+    pub fn bit(self) -> usize {
+        self as usize
+    }
+
+    /// Whether or not the flag is set in the bitset.
+    pub fn is_set(self, flags: &BitVec) -> bool {
+        flags.get(self.bit()).unwrap_or(false)
+    }
+
+    /// Name of the flag as in API.
+    pub fn name(self) -> &'static str {
+        match self {
+            UserFlag::Admin => "admin",
+            UserFlag::Moderator => "moderator",
+            UserFlag::Verified => "verified",
+            UserFlag::Banned => "banned",
+        }
+    }
+}
 
 /// User
 #[derive(Clone, Debug, FromRow)]
@@ -7,10 +48,46 @@ pub struct User {
     pub id: i64,
     /// Username
     pub username: String,
-    /// Bitset for administrative user flags
+    /// Bitset for administrative user flags, see [`UserFlag`]
     pub flags: BitVec,
 }
 
+impl User {
+    /// Whether or not the flag is set for the user.
+    pub fn has_flag(&self, flag: UserFlag) -> bool {
+        flag.is_set(&self.flags)
+    }
+
+    /// Sets or clears the flag.
+    pub fn set_flag(&mut self, flag: UserFlag, value: bool) {
+        if self.flags.len() <= flag.bit() {
+            self.flags.grow(USER_FLAGS_LEN - self.flags.len(), false);
+        }
+
+        self.flags.set(flag.bit(), value);
+    }
+
+    /// Whether or not the user is an admin.
+    pub fn is_admin(&self) -> bool {
+        self.has_flag(UserFlag::Admin)
+    }
+
+    /// Whether or not the user can moderate, admins are moderators too.
+    pub fn is_moderator(&self) -> bool {
+        self.has_flag(UserFlag::Moderator) || self.is_admin()
+    }
+
+    /// Whether or not the account is verified.
+    pub fn is_verified(&self) -> bool {
+        self.has_flag(UserFlag::Verified)
+    }
+
+    /// Whether or not the account is banned.
+    pub fn is_banned(&self) -> bool {
+        self.has_flag(UserFlag::Banned)
+    }
+}
+
 /// Email addresses used for the authentication
 #[derive(Clone, Debug, FromRow)]
 pub struct Email {
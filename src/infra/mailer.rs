@@ -0,0 +1,80 @@
+use crate::{snowflake, util::BoxFuture};
+use std::path::PathBuf;
+
+/// Outgoing mail
+#[derive(Clone, Debug)]
+pub struct Mail {
+    /// Recipient address
+    pub to: String,
+    /// Subject line
+    pub subject: String,
+    /// Plain text body
+    pub body: String,
+}
+
+/// Transport for outgoing mails.
+pub trait Mailer: Send + Sync {
+    /// Delivers the mail, returning [`None`] on failure.
+    fn send<'a>(&'a self, mail: &'a Mail) -> BoxFuture<'a, Option<()>>;
+}
+
+/// Mailer that only logs outgoing mails.
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send<'a>(&'a self, mail: &'a Mail) -> BoxFuture<'a, Option<()>> {
+        Box::pin(async move {
+            tracing::info!(
+                to = mail.to,
+                subject = mail.subject,
+                body = mail.body,
+                "Mail"
+            );
+
+            Some(())
+        })
+    }
+}
+
+/// Mailer that writes outgoing mails into a directory, one file per mail.
+///
+/// Intended for development and tests.
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    /// Creates a mailer writing into `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send<'a>(&'a self, mail: &'a Mail) -> BoxFuture<'a, Option<()>> {
+        Box::pin(async move {
+            let path = self.dir.join(format!("{}.eml", snowflake()));
+            let content = format!(
+                "To: {}\nSubject: {}\n\n{}\n",
+                mail.to, mail.subject, mail.body
+            );
+
+            let result = async {
+                tokio::fs::create_dir_all(&self.dir).await?;
+                tokio::fs::write(&path, content).await
+            }
+            .await;
+
+            match result {
+                Ok(()) => {
+                    tracing::debug!(?path, to = mail.to, "Mail written");
+                    Some(())
+                }
+                Err(err) => {
+                    tracing::error!(?err, ?path, "Could not write mail");
+                    None
+                }
+            }
+        })
+    }
+}
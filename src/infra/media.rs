@@ -0,0 +1,447 @@
+use crate::entity::AttachmentVariant;
+use image::{
+    AnimationDecoder, DynamicImage, ImageDecoder, ImageFormat, ImageReader, ImageResult, Limits,
+    codecs::{
+        gif::{GifDecoder, GifEncoder, Repeat},
+        jpeg::JpegEncoder,
+        png::PngEncoder,
+        webp::WebPDecoder,
+    },
+    imageops::FilterType,
+    metadata::Orientation,
+};
+use std::io::Cursor;
+
+/// JPEG quality of re-encoded uploads
+const JPEG_QUALITY: u8 = 90;
+
+/// Lowest JPEG quality that re-encoded uploads are reduced to, to keep them
+/// no larger than the upload
+const MIN_JPEG_QUALITY: u8 = 60;
+
+/// Step that JPEG quality of re-encoded uploads is reduced by
+const JPEG_QUALITY_STEP: u8 = 10;
+
+/// JPEG quality of generated variants
+const VARIANT_JPEG_QUALITY: u8 = 85;
+
+/// Bounding box of [`AttachmentVariant::Thumb`]
+const THUMB_SIZE: u32 = 320;
+
+/// Color quantization speed of re-encoded GIFs, from 1 (best quality) to 30
+const GIF_ENCODING_SPEED: i32 = 10;
+
+/// Maximum frame count of animated images
+pub const MAX_ANIMATION_FRAMES: usize = 256;
+
+/// Image processing failure
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaError {
+    /// Image has more pixels than allowed, possibly a decompression bomb
+    TooManyPixels,
+    /// Animated image has more than [`MAX_ANIMATION_FRAMES`] frames
+    TooManyFrames,
+    /// Image is an animated WebP, which cannot be re-encoded
+    UnsupportedAnimation,
+    /// Image could not be decoded
+    Malformed,
+}
+
+/// Uploaded image with its metadata stripped, and its variants
+pub struct ProcessedImage {
+    /// MIME type of the re-encoded image
+    pub mime_type: &'static str,
+    /// Re-encoded image
+    pub data: Vec<u8>,
+    /// Encoded variants of the image
+    pub variants: Vec<(AttachmentVariant, Vec<u8>)>,
+}
+
+/// Image format of the MIME type, if the pipeline handles it.
+pub fn image_format(mime_type: &str) -> Option<ImageFormat> {
+    match mime_type {
+        "image/png" => Some(ImageFormat::Png),
+        "image/jpeg" => Some(ImageFormat::Jpeg),
+        "image/gif" => Some(ImageFormat::Gif),
+        "image/webp" => Some(ImageFormat::WebP),
+        _ => None,
+    }
+}
+
+/// Decodes the image, strips its metadata and generates its variants in a
+/// blocking thread.
+///
+/// Images with more than `max_pixels` pixels are rejected before decoding.
+/// Animated GIFs are re-encoded frame by frame, and rejected if their frames
+/// have more than `max_pixels` pixels in total. WebP images are converted to
+/// JPEG, or to PNG if they have transparency, as WebP can only be encoded
+/// losslessly. Animated WebP images are rejected rather than flattened.
+pub async fn process_image(
+    data: Vec<u8>,
+    format: ImageFormat,
+    max_pixels: u64,
+) -> Result<ProcessedImage, MediaError> {
+    tokio::task::spawn_blocking(move || {
+        if format == ImageFormat::WebP && is_animated_webp(&data)? {
+            return Err(MediaError::UnsupportedAnimation);
+        }
+
+        let image = decode(&data, format, max_pixels)?;
+
+        let (mime_type, data) = match format {
+            ImageFormat::Gif => ("image/gif", encode_gif(&data, max_pixels)?),
+            _ => encode(&image, format, data.len()).map_err(|err| {
+                tracing::error!(?err, "Could not re-encode image");
+                MediaError::Malformed
+            })?,
+        };
+
+        let variants = AttachmentVariant::ALL
+            .into_iter()
+            .map(|variant| Ok((variant, encode_variant(&resize(&image, variant))?)))
+            .collect::<ImageResult<_>>()
+            .map_err(|err| {
+                tracing::error!(?err, "Could not encode image variant");
+                MediaError::Malformed
+            })?;
+
+        Ok(ProcessedImage {
+            mime_type,
+            data,
+            variants,
+        })
+    })
+    .await
+    .unwrap_or_else(|err| {
+        // decoders may panic on crafted input
+        tracing::error!(?err, "Image processing task failed");
+        Err(MediaError::Malformed)
+    })
+}
+
+/// Whether or not the WebP image has more than a single frame.
+fn is_animated_webp(data: &[u8]) -> Result<bool, MediaError> {
+    WebPDecoder::new(Cursor::new(data))
+        .map(|decoder| decoder.has_animation())
+        .map_err(|_| MediaError::Malformed)
+}
+
+/// Decodes the image, applying its EXIF orientation.
+fn decode(data: &[u8], format: ImageFormat, max_pixels: u64) -> Result<DynamicImage, MediaError> {
+    let (width, height) = ImageReader::with_format(Cursor::new(data), format)
+        .into_dimensions()
+        .map_err(|_| MediaError::Malformed)?;
+
+    if width as u64 * height as u64 > max_pixels {
+        return Err(MediaError::TooManyPixels);
+    }
+
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(width);
+    limits.max_image_height = Some(height);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(|_| MediaError::Malformed)?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|_| MediaError::Malformed)?;
+    image.apply_orientation(orientation);
+
+    Ok(image)
+}
+
+/// Encodes the image without any metadata, returning its MIME type.
+///
+/// JPEG images, and WebP images without transparency, are encoded as JPEG no
+/// larger than `size` bytes if possible. Others are encoded as PNG.
+fn encode(
+    image: &DynamicImage,
+    format: ImageFormat,
+    size: usize,
+) -> ImageResult<(&'static str, Vec<u8>)> {
+    match format {
+        ImageFormat::Jpeg => Ok(("image/jpeg", encode_jpeg(image, size)?)),
+        ImageFormat::WebP if !image.color().has_alpha() => {
+            Ok(("image/jpeg", encode_jpeg(image, size)?))
+        }
+        _ => Ok(("image/png", encode_png(image)?)),
+    }
+}
+
+/// Encodes the image as JPEG, lowering its quality from [`JPEG_QUALITY`]
+/// down to [`MIN_JPEG_QUALITY`] until it is no larger than `size` bytes.
+fn encode_jpeg(image: &DynamicImage, size: usize) -> ImageResult<Vec<u8>> {
+    let image = DynamicImage::from(image.to_rgb8());
+    let mut quality = JPEG_QUALITY;
+
+    loop {
+        let mut data = Vec::new();
+        image.write_with_encoder(JpegEncoder::new_with_quality(&mut data, quality))?;
+
+        if data.len() <= size || quality <= MIN_JPEG_QUALITY {
+            return Ok(data);
+        }
+
+        quality = quality
+            .saturating_sub(JPEG_QUALITY_STEP)
+            .max(MIN_JPEG_QUALITY);
+    }
+}
+
+/// Encodes the image as PNG.
+fn encode_png(image: &DynamicImage) -> ImageResult<Vec<u8>> {
+    let mut data = Vec::new();
+    image.write_with_encoder(PngEncoder::new(&mut data))?;
+
+    Ok(data)
+}
+
+/// Re-encodes every frame of the GIF, dropping its extensions such as
+/// comments and XMP metadata. The result loops forever.
+fn encode_gif(data: &[u8], max_pixels: u64) -> Result<Vec<u8>, MediaError> {
+    let decoder = GifDecoder::new(Cursor::new(data)).map_err(|_| MediaError::Malformed)?;
+    let (width, height) = decoder.dimensions();
+    let frame_pixels = width as u64 * height as u64;
+
+    let mut encoded = Vec::new();
+    let mut encoder = GifEncoder::new_with_speed(&mut encoded, GIF_ENCODING_SPEED);
+    let encoding_error = |err| {
+        tracing::error!(?err, "Could not re-encode GIF");
+        MediaError::Malformed
+    };
+
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(encoding_error)?;
+
+    for (index, frame) in decoder.into_frames().enumerate() {
+        if index >= MAX_ANIMATION_FRAMES {
+            return Err(MediaError::TooManyFrames);
+        }
+
+        if (index as u64 + 1) * frame_pixels > max_pixels {
+            return Err(MediaError::TooManyPixels);
+        }
+
+        let frame = frame.map_err(|_| MediaError::Malformed)?;
+        encoder.encode_frame(frame).map_err(encoding_error)?;
+    }
+
+    drop(encoder);
+
+    Ok(encoded)
+}
+
+/// Encodes variants as PNG if they have transparency, as JPEG otherwise.
+fn encode_variant(image: &DynamicImage) -> ImageResult<Vec<u8>> {
+    if image.color().has_alpha() {
+        encode_png(image)
+    } else {
+        let mut data = Vec::new();
+        DynamicImage::from(image.to_rgb8()).write_with_encoder(JpegEncoder::new_with_quality(
+            &mut data,
+            VARIANT_JPEG_QUALITY,
+        ))?;
+
+        Ok(data)
+    }
+}
+
+/// Resizes the image to the dimensions of the variant.
+fn resize(image: &DynamicImage, variant: AttachmentVariant) -> DynamicImage {
+    match variant {
+        AttachmentVariant::Avatar => image.resize_to_fill(256, 256, FilterType::Lanczos3),
+        AttachmentVariant::Banner => image.resize_to_fill(1500, 500, FilterType::Lanczos3),
+        AttachmentVariant::Thumb if image.width() <= THUMB_SIZE && image.height() <= THUMB_SIZE => {
+            image.clone()
+        }
+        AttachmentVariant::Thumb => image.resize(THUMB_SIZE, THUMB_SIZE, FilterType::Lanczos3),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Frame, GenericImageView, RgbImage, Rgba, RgbaImage, codecs::webp::WebPEncoder};
+
+    /// Lossy WebP photo of 256x192 pixels
+    const LOSSY_WEBP: &[u8] = include_bytes!("testdata/lossy.webp");
+
+    /// Animated WebP whose frames are the image of [`LOSSY_WEBP`].
+    fn animated_webp(frames: usize) -> Vec<u8> {
+        fn chunk(data: &mut Vec<u8>, name: &[u8], payload: &[u8]) {
+            data.extend_from_slice(name);
+            data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            data.extend_from_slice(payload);
+        }
+        let u24 = |value: u32| value.to_le_bytes()[..3].to_vec();
+
+        let mut chunks = Vec::new();
+        // animation flag, and canvas size
+        chunk(
+            &mut chunks,
+            b"VP8X",
+            &[vec![0x02, 0, 0, 0], u24(255), u24(191)].concat(),
+        );
+        // background color, and loop count
+        chunk(&mut chunks, b"ANIM", &[0; 6]);
+        for _ in 0..frames {
+            // offset, size, duration and flags, followed by the VP8 chunk
+            let header = [u24(0), u24(0), u24(255), u24(191), u24(100), vec![0]].concat();
+            chunk(&mut chunks, b"ANMF", &[&header, &LOSSY_WEBP[12..]].concat());
+        }
+
+        let mut data = Vec::new();
+        chunk(&mut data, b"RIFF", &[b"WEBP".as_slice(), &chunks].concat());
+
+        data
+    }
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        DynamicImage::from(RgbImage::new(width, height))
+            .write_with_encoder(JpegEncoder::new(&mut data))
+            .unwrap();
+
+        data
+    }
+
+    /// Animated GIF with `frames` frames, followed by a comment extension if
+    /// `comment` is set.
+    fn gif(width: u32, height: u32, frames: usize, comment: bool) -> Vec<u8> {
+        let mut data = Vec::new();
+        GifEncoder::new(&mut data)
+            .encode_frames((0..frames).map(|index| {
+                Frame::new(RgbaImage::from_pixel(
+                    width,
+                    height,
+                    Rgba([index as u8, 0, 0, 255]),
+                ))
+            }))
+            .unwrap();
+
+        if comment {
+            let trailer = data.pop().unwrap();
+            data.extend_from_slice(b"\x21\xfe\x07comment\x00");
+            data.push(trailer);
+        }
+
+        data
+    }
+
+    #[tokio::test]
+    async fn pipeline() {
+        let processed = process_image(jpeg(640, 480), ImageFormat::Jpeg, 1 << 20)
+            .await
+            .unwrap();
+        assert_eq!(processed.mime_type, "image/jpeg");
+
+        assert_eq!(
+            image::load_from_memory(&processed.data)
+                .unwrap()
+                .dimensions(),
+            (640, 480)
+        );
+
+        let dimensions: Vec<_> = processed
+            .variants
+            .iter()
+            .map(|(variant, data)| {
+                (
+                    *variant,
+                    image::load_from_memory(data).unwrap().dimensions(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            dimensions,
+            [
+                (AttachmentVariant::Avatar, (256, 256)),
+                (AttachmentVariant::Banner, (1500, 500)),
+                (AttachmentVariant::Thumb, (320, 240)),
+            ]
+        );
+
+        let processed = process_image(gif(64, 64, 4, true), ImageFormat::Gif, 1 << 20)
+            .await
+            .unwrap();
+        assert_eq!(processed.mime_type, "image/gif");
+        assert!(!processed.data.windows(7).any(|window| window == b"comment"));
+        let frames = GifDecoder::new(Cursor::new(&processed.data))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 4);
+
+        assert_eq!(
+            process_image(gif(64, 64, 4, false), ImageFormat::Gif, 64 * 64 * 3)
+                .await
+                .err(),
+            Some(MediaError::TooManyPixels)
+        );
+        assert_eq!(
+            process_image(
+                gif(1, 1, MAX_ANIMATION_FRAMES + 1, false),
+                ImageFormat::Gif,
+                1 << 20
+            )
+            .await
+            .err(),
+            Some(MediaError::TooManyFrames)
+        );
+
+        assert_eq!(
+            process_image(jpeg(2048, 2048), ImageFormat::Jpeg, 1 << 20)
+                .await
+                .err(),
+            Some(MediaError::TooManyPixels)
+        );
+        assert_eq!(
+            process_image(b"\xff\xd8\xff\xe0".to_vec(), ImageFormat::Jpeg, 1 << 20)
+                .await
+                .err(),
+            Some(MediaError::Malformed)
+        );
+    }
+
+    #[tokio::test]
+    async fn webp() {
+        // An upload at the size limit should still fit once re-encoded, it
+        // grows several times if encoded losslessly.
+        let processed = process_image(LOSSY_WEBP.to_vec(), ImageFormat::WebP, 1 << 20)
+            .await
+            .unwrap();
+        assert_eq!(processed.mime_type, "image/jpeg");
+        assert!(processed.data.len() <= LOSSY_WEBP.len());
+        assert_eq!(
+            image::load_from_memory_with_format(&processed.data, ImageFormat::Jpeg)
+                .unwrap()
+                .dimensions(),
+            (256, 192)
+        );
+
+        let mut data = Vec::new();
+        DynamicImage::from(RgbaImage::from_pixel(64, 64, Rgba([0, 0, 0, 128])))
+            .write_with_encoder(WebPEncoder::new_lossless(&mut data))
+            .unwrap();
+        let processed = process_image(data, ImageFormat::WebP, 1 << 20)
+            .await
+            .unwrap();
+        assert_eq!(processed.mime_type, "image/png");
+        assert!(
+            image::load_from_memory_with_format(&processed.data, ImageFormat::Png)
+                .unwrap()
+                .color()
+                .has_alpha()
+        );
+
+        assert_eq!(
+            process_image(animated_webp(2), ImageFormat::WebP, 1 << 20)
+                .await
+                .err(),
+            Some(MediaError::UnsupportedAnimation)
+        );
+    }
+}
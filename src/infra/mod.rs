@@ -3,3 +3,12 @@ pub mod service;
 
 /// Data access repository
 pub mod repository;
+
+/// Outgoing mail transports
+pub mod mailer;
+
+/// File storage backends
+pub mod storage;
+
+/// Image decoding and variant generation
+pub mod media;
@@ -0,0 +1,147 @@
+use crate::{entity, state::Database};
+use indoc::indoc;
+use sqlx::PgTransaction;
+
+/// Attachment data access repository
+pub struct AttachmentRepository {
+    db: Database,
+}
+
+impl AttachmentRepository {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    pub async fn get_attachment_by_id(&self, id: i64) -> Option<entity::Attachment> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT id, user_id, mime_type, size, checksum
+                FROM attachments
+                WHERE id = $1"
+            })
+            .bind(id)
+        )
+    }
+
+    pub async fn create_attachment(&self, attachment: entity::Attachment) -> Option<()> {
+        unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query(indoc! {
+                "INSERT INTO attachments (id, user_id, mime_type, size, checksum)
+                    VALUES
+                ($1, $2, $3, $4, $5)"
+            })
+            .bind(attachment.id)
+            .bind(attachment.user_id)
+            .bind(attachment.mime_type)
+            .bind(attachment.size)
+            .bind(attachment.checksum)
+        )?;
+
+        Some(())
+    }
+
+    pub async fn delete_attachment(&self, id: i64) -> Option<()> {
+        let result = unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query("DELETE FROM attachments WHERE id = $1").bind(id)
+        )?;
+
+        (result.rows_affected() > 0).then_some(())
+    }
+
+    /// Locks the attachments until the end of the transaction, so that they
+    /// are not collected while the transaction references them.
+    ///
+    /// Returns `false` if any of the attachments does not exist.
+    pub async fn lock_attachments(&self, tx: &mut PgTransaction<'_>, ids: &[i64]) -> bool {
+        if ids.is_empty() {
+            return true;
+        }
+
+        let mut ids = ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+
+        let locked: Vec<i64> = unwrap_fetch_all!(
+            &mut **tx,
+            sqlx::query_scalar("SELECT id FROM attachments WHERE id = ANY($1) FOR KEY SHARE")
+                .bind(&ids)
+        );
+
+        locked.len() == ids.len()
+    }
+
+    /// Ids of attachments older than the grace period that are neither
+    /// attached to a post nor used as an avatar or banner, in ascending order
+    /// after `after`.
+    pub async fn get_orphaned_attachment_ids(
+        &self,
+        grace_period: u64,
+        after: i64,
+        limit: i64,
+    ) -> Vec<i64> {
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_scalar(indoc! {
+                "SELECT id FROM attachments
+                WHERE id < snowflake_like_base_past($1::interval)
+                    AND id > $2
+                    AND NOT EXISTS (
+                        SELECT 1 FROM posts WHERE attachments @> ARRAY[attachments.id]
+                    )
+                    AND NOT EXISTS (
+                        SELECT 1 FROM profiles
+                        WHERE avatar_id = attachments.id OR banner_id = attachments.id
+                    )
+                ORDER BY id
+                LIMIT $3"
+            })
+            .bind(format!("{} seconds", grace_period))
+            .bind(after)
+            .bind(limit)
+        )
+    }
+
+    /// Locks the attachment for deletion, waiting for transactions that
+    /// reference it to end.
+    pub async fn lock_attachment_for_deletion(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        id: i64,
+    ) -> Option<()> {
+        unwrap_fetch_one!(
+            &mut **tx,
+            sqlx::query_scalar::<_, i64>("SELECT id FROM attachments WHERE id = $1 FOR UPDATE")
+                .bind(id)
+        )
+        .map(|_| ())
+    }
+
+    /// Deletes the attachment unless it has been referenced since it was
+    /// found orphaned.
+    ///
+    /// The attachment should be locked with
+    /// [`Self::lock_attachment_for_deletion`] beforehand, so that references
+    /// committed meanwhile are seen.
+    pub async fn delete_attachment_if_orphaned(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        id: i64,
+    ) -> Option<bool> {
+        unwrap_execute!(
+            &mut **tx,
+            sqlx::query(indoc! {
+                "DELETE FROM attachments
+                WHERE id = $1
+                    AND NOT EXISTS (SELECT 1 FROM posts WHERE attachments @> ARRAY[$1])
+                    AND NOT EXISTS (
+                        SELECT 1 FROM profiles WHERE avatar_id = $1 OR banner_id = $1
+                    )"
+            })
+            .bind(id)
+        )
+        .map(|result| result.rows_affected() > 0)
+    }
+}
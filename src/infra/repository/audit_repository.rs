@@ -0,0 +1,72 @@
+use crate::{entity, state::Database};
+use indoc::indoc;
+use sqlx::PgTransaction;
+
+/// Audit log data access repository
+pub struct AuditRepository {
+    db: Database,
+}
+
+impl AuditRepository {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    pub async fn create_audit_entry(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        entry: entity::AuditEntry,
+    ) -> Option<()> {
+        unwrap_execute!(
+            &mut **tx,
+            sqlx::query(indoc! {
+                "INSERT INTO audit_log (id, actor_id, action, target_id, details)
+                    VALUES
+                ($1, $2, $3, $4, $5)"
+            })
+            .bind(entry.id)
+            .bind(entry.actor_id)
+            .bind(entry.action)
+            .bind(entry.target_id)
+            .bind(entry.details)
+        )?;
+
+        Some(())
+    }
+
+    pub async fn get_audit_entries_of_target(&self, target_id: i64) -> Vec<entity::AuditEntry> {
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT id, actor_id, action, target_id, details
+                FROM audit_log
+                WHERE target_id = $1
+                ORDER BY id DESC
+                LIMIT 32"
+            })
+            .bind(target_id)
+        )
+    }
+
+    pub async fn get_audit_entries(
+        &self,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<entity::AuditEntry> {
+        let limit = std::cmp::min(limit.unwrap_or(32), 32) as i64;
+        let before = before.unwrap_or(i64::MAX);
+
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT id, actor_id, action, target_id, details
+                FROM audit_log
+                WHERE id < $1
+                ORDER BY id DESC
+                LIMIT $2"
+            })
+            .bind(before)
+            .bind(limit)
+        )
+    }
+}
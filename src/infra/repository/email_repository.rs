@@ -0,0 +1,166 @@
+use crate::{entity, state::Database};
+use indoc::indoc;
+
+/// Email data access repository
+pub struct EmailRepository {
+    db: Database,
+}
+
+impl EmailRepository {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    pub async fn get_email_by_id(&self, id: i64) -> Option<entity::Email> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as("SELECT id, user_id, email, is_verified FROM emails WHERE id = $1")
+                .bind(id)
+        )
+    }
+
+    pub async fn get_verified_email(&self, email: &str) -> Option<entity::Email> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT id, user_id, email, is_verified
+                FROM emails
+                WHERE email = $1 AND is_verified"
+            })
+            .bind(email)
+        )
+    }
+
+    pub async fn get_emails_of_user(&self, user_id: i64) -> Vec<entity::Email> {
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT id, user_id, email, is_verified
+                FROM emails
+                WHERE user_id = $1
+                ORDER BY id"
+            })
+            .bind(user_id)
+        )
+    }
+
+    pub async fn count_emails_of_user(&self, user_id: i64) -> Option<i64> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as::<_, (i64,)>("SELECT COUNT(1) FROM emails WHERE user_id = $1")
+                .bind(user_id)
+        )
+        .map(|row| row.0)
+    }
+
+    /// Returns `false` if the user has already added the email.
+    pub async fn create_email(&self, email: entity::Email) -> Option<bool> {
+        unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query(indoc! {
+                "INSERT INTO emails (id, user_id, email, is_verified)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (user_id, email) DO NOTHING"
+            })
+            .bind(email.id)
+            .bind(email.user_id)
+            .bind(email.email)
+            .bind(email.is_verified)
+        )
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    /// Marks the email as verified, if it still belongs to the user, has not
+    /// been changed and is not verified by another account.
+    ///
+    /// Unverified claims of other accounts on the same address are removed.
+    pub async fn verify_email(&self, id: i64, user_id: i64, email: &str) -> Option<entity::Email> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "WITH verified AS (
+                    UPDATE emails
+                    SET is_verified = true
+                    WHERE id = $1 AND user_id = $2 AND email = $3
+                        AND NOT EXISTS (
+                            SELECT 1 FROM emails
+                            WHERE email = $3 AND is_verified AND id != $1
+                        )
+                    RETURNING id, user_id, email, is_verified
+                ), released AS (
+                    DELETE FROM emails
+                    WHERE email = $3 AND NOT is_verified AND id != $1
+                        AND EXISTS (SELECT 1 FROM verified)
+                )
+                SELECT id, user_id, email, is_verified FROM verified"
+            })
+            .bind(id)
+            .bind(user_id)
+            .bind(email)
+        )
+    }
+
+    pub async fn delete_email(&self, id: i64, user_id: i64) -> Option<bool> {
+        unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query("DELETE FROM emails WHERE id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(user_id)
+        )
+        .map(|result| result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{snowflake, testutil::test_db};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn email_lifecycle() {
+        let repo = EmailRepository::new(test_db().await);
+
+        let id = snowflake();
+        let email = format!("{}@example.com", snowflake());
+        let new_email = |id| entity::Email {
+            id,
+            user_id: 1001,
+            email: email.clone(),
+            is_verified: false,
+        };
+
+        assert!(repo.create_email(new_email(id)).await.unwrap());
+        assert!(!repo.create_email(new_email(snowflake())).await.unwrap());
+
+        let claim_id = snowflake();
+        assert!(
+            repo.create_email(entity::Email {
+                user_id: 1002,
+                ..new_email(claim_id)
+            })
+            .await
+            .unwrap()
+        );
+
+        let count = repo.count_emails_of_user(1001).await.unwrap();
+        assert!(count >= 1);
+        assert_eq!(repo.get_emails_of_user(1001).await.len() as i64, count);
+
+        assert!(repo.verify_email(id, 1002, &email).await.is_none());
+        assert!(
+            repo.verify_email(id, 1001, &email)
+                .await
+                .unwrap()
+                .is_verified
+        );
+        assert!(repo.get_email_by_id(id).await.unwrap().is_verified);
+        assert!(repo.get_email_by_id(claim_id).await.is_none());
+        assert_eq!(repo.get_verified_email(&email).await.unwrap().id, id);
+
+        assert!(!repo.delete_email(id, 1002).await.unwrap());
+        assert!(repo.delete_email(id, 1001).await.unwrap());
+        assert!(repo.get_email_by_id(id).await.is_none());
+    }
+}
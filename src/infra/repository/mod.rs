@@ -46,6 +46,22 @@ mod post_repository;
 #[allow(missing_docs)]
 mod thread_repository;
 
+#[allow(missing_docs)]
+mod email_repository;
+
+#[allow(missing_docs)]
+mod attachment_repository;
+
+#[allow(missing_docs)]
+mod audit_repository;
+
+#[allow(missing_docs)]
+mod report_repository;
+
+pub use attachment_repository::AttachmentRepository;
+pub use audit_repository::AuditRepository;
+pub use email_repository::EmailRepository;
 pub use post_repository::PostRepository;
+pub use report_repository::ReportRepository;
 pub use thread_repository::ThreadRepository;
 pub use user_repository::UserRepository;
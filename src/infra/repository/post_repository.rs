@@ -1,5 +1,10 @@
-use crate::{dto::posts::PostStatsDto, entity, state::Database};
+use crate::{
+    dto::{posts::PostStatsDto, user::UserDto},
+    entity, snowflake,
+    state::Database,
+};
 use indoc::indoc;
+use sqlx::PgTransaction;
 
 /// Post data access repository
 pub struct PostRepository {
@@ -18,7 +23,7 @@ impl PostRepository {
                 "SELECT
                     id, user_id, thread_id, replies_thread_id, content, is_edited, attachments
                 FROM posts
-                WHERE id = $1"
+                WHERE id = $1 AND NOT is_hidden"
             })
             .bind(id)
         )
@@ -31,7 +36,7 @@ impl PostRepository {
                 "SELECT
                     COALESCE((
                         SELECT COUNT(1) FROM posts AS posts_
-                        WHERE posts_.thread_id = posts.replies_thread_id
+                        WHERE posts_.thread_id = posts.replies_thread_id AND NOT posts_.is_hidden
                         GROUP BY thread_id
                     ), 0) as comments,
                         COALESCE((
@@ -39,11 +44,202 @@ impl PostRepository {
                         WHERE likes.post_id = posts.id
                         GROUP BY post_id
                     ), 0) as likes
-                FROM posts WHERE posts.id = $1"
+                FROM posts WHERE posts.id = $1 AND NOT posts.is_hidden"
+            })
+            .bind(id)
+        )
+    }
+
+    pub async fn create_post(&self, tx: &mut PgTransaction<'_>, post: entity::Post) -> Option<()> {
+        unwrap_execute!(
+            &mut **tx,
+            sqlx::query(indoc! {
+                "INSERT INTO posts
+                    (id, user_id, thread_id, replies_thread_id, content, is_edited, attachments)
+                VALUES
+                    ($1, $2, $3, $4, $5, $6, $7)"
+            })
+            .bind(post.id)
+            .bind(post.user_id)
+            .bind(post.thread_id)
+            .bind(post.replies_thread_id)
+            .bind(post.content)
+            .bind(post.is_edited)
+            .bind(post.attachments)
+        )?;
+
+        Some(())
+    }
+
+    pub async fn update_post(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        id: i64,
+        content: &str,
+        attachments: &[i64],
+    ) -> Option<entity::Post> {
+        unwrap_fetch_one!(
+            &mut **tx,
+            sqlx::query_as(indoc! {
+                "UPDATE posts
+                SET content = $2, attachments = $3, is_edited = true
+                WHERE id = $1
+                RETURNING
+                    id, user_id, thread_id, replies_thread_id, content, is_edited, attachments"
+            })
+            .bind(id)
+            .bind(content)
+            .bind(attachments)
+        )
+    }
+
+    /// Deletes the post with its likes and reports.
+    pub async fn delete_post(&self, tx: &mut PgTransaction<'_>, id: i64) -> Option<()> {
+        for query in [
+            "DELETE FROM relations.likes WHERE post_id = $1",
+            "DELETE FROM reports WHERE target_type = 'post' AND target_id = $1",
+        ] {
+            unwrap_execute!(&mut **tx, sqlx::query(query).bind(id))?;
+        }
+
+        let result = unwrap_execute!(
+            &mut **tx,
+            sqlx::query("DELETE FROM posts WHERE id = $1").bind(id)
+        )?;
+
+        (result.rows_affected() > 0).then_some(())
+    }
+
+    /// Hides or unhides the post, returns `false` if the post already has
+    /// the visibility.
+    pub async fn set_post_hidden(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        id: i64,
+        hidden: bool,
+    ) -> Option<bool> {
+        unwrap_execute!(
+            &mut **tx,
+            sqlx::query("UPDATE posts SET is_hidden = $2 WHERE id = $1 AND is_hidden <> $2")
+                .bind(id)
+                .bind(hidden)
+        )
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    /// Fetches the post, even if hidden.
+    pub async fn get_post_including_hidden(&self, id: i64) -> Option<entity::Post> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT
+                    id, user_id, thread_id, replies_thread_id, content, is_edited, attachments
+                FROM posts
+                WHERE id = $1"
             })
             .bind(id)
         )
     }
+
+    /// Deletes every post of the user with their likes and reports.
+    pub async fn delete_posts_of_user(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        user_id: i64,
+    ) -> Option<()> {
+        for query in [
+            indoc! {
+                "DELETE FROM relations.likes
+                WHERE post_id IN (SELECT id FROM posts WHERE user_id = $1)"
+            },
+            indoc! {
+                "DELETE FROM reports
+                WHERE target_type = 'post'
+                    AND target_id IN (SELECT id FROM posts WHERE user_id = $1)"
+            },
+        ] {
+            unwrap_execute!(&mut **tx, sqlx::query(query).bind(user_id))?;
+        }
+
+        unwrap_execute!(
+            &mut **tx,
+            sqlx::query("DELETE FROM posts WHERE user_id = $1").bind(user_id)
+        )?;
+
+        Some(())
+    }
+
+    /// Hands every post of the user over to the placeholder user `0`.
+    pub async fn anonymise_posts_of_user(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        user_id: i64,
+    ) -> Option<()> {
+        unwrap_execute!(
+            &mut **tx,
+            sqlx::query("UPDATE posts SET user_id = 0 WHERE user_id = $1").bind(user_id)
+        )?;
+
+        Some(())
+    }
+
+    /// Likes the post, does nothing if the user already liked it.
+    pub async fn like_post(&self, user_id: i64, post_id: i64) -> Option<()> {
+        unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query(indoc! {
+                "INSERT INTO relations.likes (id, user_id, post_id)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (user_id, post_id) DO NOTHING"
+            })
+            .bind(snowflake())
+            .bind(user_id)
+            .bind(post_id)
+        )?;
+
+        Some(())
+    }
+
+    pub async fn unlike_post(&self, user_id: i64, post_id: i64) -> Option<()> {
+        unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query("DELETE FROM relations.likes WHERE user_id = $1 AND post_id = $2")
+                .bind(user_id)
+                .bind(post_id)
+        )?;
+
+        Some(())
+    }
+
+    pub async fn get_likes(
+        &self,
+        post_id: i64,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<UserDto> {
+        let limit = std::cmp::min(limit.unwrap_or(32), 32) as i64;
+        let before = before.unwrap_or(i64::MAX);
+
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT
+                    user_id AS id, username, flags, (
+                        SELECT avatar_id
+                        FROM profiles
+                        WHERE profiles.user_id = users.id
+                    )
+                FROM relations.likes
+                RIGHT JOIN users ON users.id = likes.user_id
+                WHERE post_id = $1 AND user_id < $2
+                ORDER BY user_id DESC
+                LIMIT $3"
+            })
+            .bind(post_id)
+            .bind(before)
+            .bind(limit)
+        )
+    }
 }
 
 #[cfg(test)]
@@ -61,4 +257,26 @@ mod tests {
             repo.get_post_by_id(i + 4000).await.unwrap();
         }
     }
+
+    #[serial]
+    #[tokio::test]
+    async fn likes() {
+        let repo = PostRepository::new(test_db().await);
+
+        let likes = repo.get_post_stats_by_id(4002).await.unwrap().likes;
+
+        repo.like_post(1001, 4002).await.unwrap();
+        repo.like_post(1001, 4002).await.unwrap();
+
+        assert_eq!(
+            repo.get_post_stats_by_id(4002).await.unwrap().likes,
+            likes + 1
+        );
+        assert_eq!(repo.get_likes(4002, None, None).await[0].id, 1001);
+        assert!(repo.get_likes(4002, None, Some(1001)).await.is_empty());
+
+        repo.unlike_post(1001, 4002).await.unwrap();
+
+        assert_eq!(repo.get_post_stats_by_id(4002).await.unwrap().likes, likes);
+    }
 }
@@ -0,0 +1,105 @@
+use crate::{entity, state::Database};
+use indoc::indoc;
+use sqlx::PgTransaction;
+
+/// Report data access repository
+pub struct ReportRepository {
+    db: Database,
+}
+
+impl ReportRepository {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Returns `false` if the reporter has already reported the target.
+    pub async fn create_report(&self, report: entity::Report) -> Option<bool> {
+        unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query(indoc! {
+                "INSERT INTO reports (id, reporter_id, target_type, target_id, reason, details)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (reporter_id, target_type, target_id) DO NOTHING"
+            })
+            .bind(report.id)
+            .bind(report.reporter_id)
+            .bind(report.target_type)
+            .bind(report.target_id)
+            .bind(report.reason)
+            .bind(report.details)
+        )
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    pub async fn get_reports_of_target(
+        &self,
+        target_type: entity::ReportTarget,
+        target_id: i64,
+    ) -> Vec<entity::Report> {
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT id, reporter_id, target_type, target_id, reason, details
+                FROM reports
+                WHERE target_type = $1 AND target_id = $2
+                ORDER BY id DESC
+                LIMIT 32"
+            })
+            .bind(target_type)
+            .bind(target_id)
+        )
+    }
+
+    /// Deletes reports of the target, returning the number of deleted reports.
+    pub async fn delete_reports_of_target(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        target_type: entity::ReportTarget,
+        target_id: i64,
+    ) -> Option<u64> {
+        unwrap_execute!(
+            &mut **tx,
+            sqlx::query("DELETE FROM reports WHERE target_type = $1 AND target_id = $2")
+                .bind(target_type)
+                .bind(target_id)
+        )
+        .map(|result| result.rows_affected())
+    }
+
+    /// Reports grouped by their target, most reported targets first.
+    ///
+    /// Groups are paginated by `before`, the report count and last report id
+    /// of the last group of the previous page.
+    pub async fn get_report_groups(
+        &self,
+        target_type: Option<entity::ReportTarget>,
+        limit: Option<u64>,
+        before: Option<(u64, i64)>,
+    ) -> Vec<entity::ReportGroup> {
+        let limit = std::cmp::min(limit.unwrap_or(32), 32) as i64;
+        let (before_count, before_id) = before.map_or((i64::MAX, i64::MAX), |(count, id)| {
+            (count.min(i64::MAX as u64) as i64, id)
+        });
+
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT
+                    target_type, target_id,
+                    COUNT(1) AS report_count,
+                    array_agg(DISTINCT reason) AS reasons,
+                    MAX(id) AS last_report_id
+                FROM reports
+                WHERE $1::varchar IS NULL OR target_type = $1
+                GROUP BY target_type, target_id
+                HAVING (COUNT(1), MAX(id)) < ($3, $4)
+                ORDER BY report_count DESC, last_report_id DESC
+                LIMIT $2"
+            })
+            .bind(target_type)
+            .bind(limit)
+            .bind(before_count)
+            .bind(before_id)
+        )
+    }
+}
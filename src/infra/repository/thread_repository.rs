@@ -2,6 +2,12 @@ use crate::{entity, state::Database};
 use indoc::indoc;
 use sqlx::PgTransaction;
 
+/// Replies fetched per post below the first level of reply trees
+const REPLY_BRANCH_LIMIT: i64 = 4;
+
+/// Maximum levels walked up while looking for ancestors of a reply
+const MAX_ANCESTOR_DEPTH: i32 = 256;
+
 /// Thread data access repository
 pub struct ThreadRepository {
     db: Database,
@@ -43,9 +49,94 @@ impl ThreadRepository {
         Some(())
     }
 
+    /// Deletes the thread if there is no post in it.
+    pub async fn delete_thread_if_empty(&self, tx: &mut PgTransaction<'_>, id: i64) -> Option<()> {
+        unwrap_execute!(
+            &mut **tx,
+            sqlx::query(indoc! {
+                "DELETE FROM threads
+                WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM posts WHERE thread_id = $1)"
+            })
+            .bind(id)
+        )?;
+
+        Some(())
+    }
+
+    /// Deletes threads of the user that are neither referenced nor contain
+    /// any posts.
+    pub async fn delete_empty_threads_of_user(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        user_id: i64,
+    ) -> Option<()> {
+        unwrap_execute!(
+            &mut **tx,
+            sqlx::query(indoc! {
+                "DELETE FROM threads
+                WHERE user_id = $1
+                    AND NOT EXISTS (SELECT 1 FROM posts WHERE thread_id = threads.id)
+                    AND NOT EXISTS (SELECT 1 FROM posts WHERE replies_thread_id = threads.id)
+                    AND NOT EXISTS (
+                        SELECT 1 FROM profiles WHERE comments_thread_id = threads.id
+                    )"
+            })
+            .bind(user_id)
+        )?;
+
+        Some(())
+    }
+
+    /// Id of the user whose profile wall is the thread.
+    pub async fn get_wall_owner_id(&self, thread_id: i64) -> Option<i64> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_scalar("SELECT user_id FROM profiles WHERE comments_thread_id = $1")
+                .bind(thread_id)
+        )
+    }
+
+    /// Posts whose replies thread contains the post, or one of its
+    /// ancestors, root first.
+    ///
+    /// At most [`MAX_ANCESTOR_DEPTH`] closest ancestors are fetched, the
+    /// returned flag is set if there are more.
+    pub async fn get_ancestors(&self, post_id: i64) -> (Vec<entity::Ancestor>, bool) {
+        // One more level than returned is walked to tell if there are more.
+        let mut ancestors: Vec<entity::Ancestor> = unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "WITH RECURSIVE ancestors AS (
+                    SELECT thread_id, 0 AS depth FROM posts WHERE id = $1
+                    UNION ALL
+                    SELECT posts.thread_id, ancestors.depth + 1 FROM ancestors
+                    INNER JOIN posts ON posts.replies_thread_id = ancestors.thread_id
+                    WHERE ancestors.depth < $2
+                )
+                SELECT
+                    posts.id, posts.user_id, posts.thread_id, posts.replies_thread_id,
+                    posts.content, posts.is_edited, posts.attachments, posts.is_hidden
+                FROM ancestors
+                INNER JOIN posts ON posts.replies_thread_id = ancestors.thread_id
+                ORDER BY ancestors.depth DESC"
+            })
+            .bind(post_id)
+            .bind(MAX_ANCESTOR_DEPTH)
+        );
+
+        let truncated = ancestors.len() > MAX_ANCESTOR_DEPTH as usize;
+        if truncated {
+            ancestors.remove(0);
+        }
+
+        (ancestors, truncated)
+    }
+
+    /// Posts of the users blocked or muted by the viewer are excluded.
     pub async fn get_latest_posts(
         &self,
         thread_id: Option<i64>,
+        viewer_id: Option<i64>,
         limit: Option<u64>,
         before: Option<i64>,
     ) -> Vec<entity::Post> {
@@ -59,17 +150,26 @@ impl ThreadRepository {
                 FROM posts
                 WHERE thread_id {}
                 AND id < $1
+                AND NOT is_hidden
+                AND user_id NOT IN (
+                    SELECT user_id FROM relations.blocks WHERE blocker_id = $3
+                    UNION ALL
+                    SELECT user_id FROM relations.mutes WHERE muter_id = $3
+                )
                 ORDER BY id DESC
                 LIMIT $2"
             },
             if thread_id.is_some() {
-                "= $3"
+                "= $4"
             } else {
                 "IS NULL"
             }
         );
 
-        let mut query = sqlx::query_as(&sql).bind(before).bind(limit);
+        let mut query = sqlx::query_as(&sql)
+            .bind(before)
+            .bind(limit)
+            .bind(viewer_id);
 
         if let Some(thread_id) = thread_id {
             query = query.bind(thread_id);
@@ -78,9 +178,44 @@ impl ThreadRepository {
         unwrap_fetch_all!(&self.db.pool(), query)
     }
 
+    /// Latest posts authored by the users that the follower follows, except
+    /// the muted ones.
+    pub async fn get_timeline_posts(
+        &self,
+        follower_id: i64,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<entity::Post> {
+        let limit = std::cmp::min(limit.unwrap_or(32), 32) as i64;
+        let before = before.unwrap_or(i64::MAX);
+
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT
+                    id, user_id, thread_id, replies_thread_id, content, is_edited, attachments
+                FROM posts
+                WHERE user_id IN (
+                    SELECT user_id FROM relations.follows WHERE follower_id = $1
+                    EXCEPT
+                    SELECT user_id FROM relations.mutes WHERE muter_id = $1
+                )
+                AND id < $2
+                AND NOT is_hidden
+                ORDER BY id DESC
+                LIMIT $3"
+            })
+            .bind(follower_id)
+            .bind(before)
+            .bind(limit)
+        )
+    }
+
+    /// Posts of the users blocked or muted by the viewer are excluded.
     pub async fn get_hot_posts(
         &self,
         thread_id: Option<i64>,
+        viewer_id: Option<i64>,
         time_period: Option<u64>,
     ) -> Vec<entity::Post> {
         let time_period = time_period.unwrap_or(7).clamp(1, 30);
@@ -107,6 +242,12 @@ impl ThreadRepository {
                     ON latest_likes.post_id = posts.id
                 WHERE
                     posts.id > snowflake_like_base_past($1::interval) AND
+                    NOT posts.is_hidden AND
+                    posts.user_id NOT IN (
+                        SELECT user_id FROM relations.blocks WHERE blocker_id = $2
+                        UNION ALL
+                        SELECT user_id FROM relations.mutes WHERE muter_id = $2
+                    ) AND
                     (
                         latest_replies.thread_id IS NOT NULL OR
                         latest_likes.post_id IS NOT NULL
@@ -116,13 +257,15 @@ impl ThreadRepository {
                 LIMIT 32"
             },
             if thread_id.is_some() {
-                "= $2"
+                "= $3"
             } else {
                 "IS NULL"
             }
         );
 
-        let mut query = sqlx::query_as(&sql).bind(format!("{} days", time_period));
+        let mut query = sqlx::query_as(&sql)
+            .bind(format!("{} days", time_period))
+            .bind(viewer_id);
 
         if let Some(thread_id) = thread_id {
             query = query.bind(thread_id);
@@ -130,6 +273,79 @@ impl ThreadRepository {
 
         unwrap_fetch_all!(&self.db.pool(), query)
     }
+
+    /// Latest replies in the thread, each with its latest replies down to
+    /// `depth` levels, ordered by depth. Replies below the first level are
+    /// limited per post.
+    ///
+    /// Posts of the users blocked or muted by the viewer are excluded.
+    pub async fn get_reply_tree(
+        &self,
+        thread_id: i64,
+        viewer_id: Option<i64>,
+        depth: Option<u64>,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<entity::Reply> {
+        let depth = depth.unwrap_or(2).clamp(1, 3) as i32;
+        let limit = std::cmp::min(limit.unwrap_or(32), 32) as i64;
+        let before = before.unwrap_or(i64::MAX);
+
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "WITH RECURSIVE
+                    hidden_authors AS (
+                        SELECT user_id FROM relations.blocks WHERE blocker_id = $6
+                        UNION ALL
+                        SELECT user_id FROM relations.mutes WHERE muter_id = $6
+                    ),
+                    tree AS (
+                        SELECT replies.*, 1 AS depth FROM (
+                            SELECT
+                                id, user_id, thread_id, replies_thread_id, content, is_edited,
+                                attachments
+                            FROM posts
+                            WHERE thread_id = $1
+                                AND id < $2
+                                AND NOT is_hidden
+                                AND user_id NOT IN (SELECT user_id FROM hidden_authors)
+                            ORDER BY id DESC
+                            LIMIT $3
+                        ) AS replies
+                        UNION ALL
+                        SELECT replies.*, tree.depth + 1 FROM tree
+                        CROSS JOIN LATERAL (
+                            SELECT
+                                id, user_id, thread_id, replies_thread_id, content, is_edited,
+                                attachments
+                            FROM posts
+                            WHERE thread_id = tree.replies_thread_id
+                                AND NOT is_hidden
+                                AND user_id NOT IN (SELECT user_id FROM hidden_authors)
+                            ORDER BY id DESC
+                            LIMIT $4
+                        ) AS replies
+                        WHERE tree.depth < $5
+                    )
+                SELECT tree.*, EXISTS (
+                    SELECT 1 FROM posts
+                    WHERE posts.thread_id = tree.replies_thread_id
+                        AND NOT posts.is_hidden
+                        AND posts.user_id NOT IN (SELECT user_id FROM hidden_authors)
+                        AND NOT EXISTS (SELECT 1 FROM tree AS children WHERE children.id = posts.id)
+                ) AS has_more_replies
+                FROM tree
+                ORDER BY depth, id DESC"
+            })
+            .bind(thread_id)
+            .bind(before)
+            .bind(limit)
+            .bind(REPLY_BRANCH_LIMIT)
+            .bind(depth)
+            .bind(viewer_id)
+        )
+    }
 }
 
 #[cfg(test)]
@@ -148,18 +364,29 @@ mod tests {
             repo.get_thread_by_id(i + 3000).await.unwrap();
         }
 
-        let posts = repo.get_latest_posts(None, Some(10), None).await;
+        let posts = repo.get_latest_posts(None, None, Some(10), None).await;
 
         for i in 1..=10i64 {
             assert_eq!(posts[i as usize - 1].id, 4021 - i);
         }
 
-        let posts = repo.get_latest_posts(None, Some(5), Some(4011)).await;
+        let posts = repo.get_latest_posts(None, None, Some(5), Some(4011)).await;
 
         for i in 1..=5i64 {
             assert_eq!(posts[i as usize - 1].id, 4011 - i);
         }
 
-        repo.get_hot_posts(None, Some(7)).await;
+        repo.get_hot_posts(None, None, Some(7)).await;
+
+        // user21 follows user01, user02 and user03
+        let posts = repo.get_timeline_posts(1021, None, None).await;
+        let ids: Vec<_> = posts.iter().map(|post| post.id).collect();
+
+        assert_eq!(ids, [6003, 6002, 6001, 4003, 4002, 4001]);
+
+        let posts = repo.get_timeline_posts(1021, Some(2), Some(6001)).await;
+        let ids: Vec<_> = posts.iter().map(|post| post.id).collect();
+
+        assert_eq!(ids, [4003, 4002]);
     }
 }
@@ -1,6 +1,6 @@
 use crate::{
-    dto::user::{FullProfileDto, UserDto, UserStatsDto},
-    entity,
+    dto::user::{FullProfileDto, RelationshipDto, UserDto, UserStatsDto},
+    entity, snowflake,
     state::Database,
 };
 use indoc::indoc;
@@ -25,12 +25,22 @@ impl UserRepository {
                         SELECT avatar_id
                         FROM profiles WHERE user_id = id
                     )
-                FROM users WHERE id = $1"
+                FROM users WHERE id = $1 AND deleted_at IS NULL"
             })
             .bind(id)
         )
     }
 
+    pub async fn get_user_entity_by_id(&self, id: i64) -> Option<entity::User> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as(
+                "SELECT id, username, flags FROM users WHERE id = $1 AND deleted_at IS NULL"
+            )
+            .bind(id)
+        )
+    }
+
     /// For schema validation test
     async fn __get_user_by_id(&self, id: i64) -> Option<entity::User> {
         unwrap_fetch_one!(
@@ -48,7 +58,7 @@ impl UserRepository {
                         SELECT avatar_id
                         FROM profiles WHERE user_id = id
                     )
-                FROM users WHERE username = $1"
+                FROM users WHERE username = $1 AND deleted_at IS NULL"
             })
             .bind(username)
         )
@@ -73,7 +83,7 @@ impl UserRepository {
             sqlx::query_as::<_, FullProfileDto>(indoc! {
                 "SELECT id, username, flags, comments_thread_id, avatar_id, banner_id, bio
                 FROM profiles LEFT JOIN users ON user_id = users.id
-                WHERE user_id = $1",
+                WHERE user_id = $1 AND deleted_at IS NULL",
             })
             .bind(user_id)
         )
@@ -85,7 +95,7 @@ impl UserRepository {
             sqlx::query_as::<_, FullProfileDto>(indoc! {
                 "SELECT id, username, flags, comments_thread_id, avatar_id, banner_id, bio
                 FROM profiles LEFT JOIN users ON user_id = users.id
-                WHERE username = $1",
+                WHERE username = $1 AND deleted_at IS NULL",
             })
             .bind(username)
         )
@@ -120,8 +130,10 @@ impl UserRepository {
     pub async fn get_user_password_hash_by_id(&self, id: i64) -> Option<String> {
         unwrap_fetch_one!(
             &self.db.pool(),
-            sqlx::query_as::<_, (String,)>("SELECT password_hash FROM users WHERE id = $1")
-                .bind(id)
+            sqlx::query_as::<_, (String,)>(
+                "SELECT password_hash FROM users WHERE id = $1 AND deleted_at IS NULL"
+            )
+            .bind(id)
         )
         .map(|row| row.0)
     }
@@ -129,15 +141,19 @@ impl UserRepository {
     pub async fn get_user_password_hash_by_username(&self, username: &str) -> Option<String> {
         unwrap_fetch_one!(
             &self.db.pool(),
-            sqlx::query_as::<_, (String,)>("SELECT password_hash FROM users WHERE username = $1")
-                .bind(username)
+            sqlx::query_as::<_, (String,)>(
+                "SELECT password_hash FROM users WHERE username = $1 AND deleted_at IS NULL"
+            )
+            .bind(username)
         )
         .map(|row| row.0)
     }
 
+    /// Users blocked by the viewer are excluded.
     pub async fn get_follows(
         &self,
         id: i64,
+        viewer_id: Option<i64>,
         limit: Option<u64>,
         before: Option<i64>,
     ) -> Vec<UserDto> {
@@ -156,18 +172,25 @@ impl UserRepository {
                 FROM relations.follows
                 RIGHT JOIN users ON users.id = follows.user_id
                 WHERE follower_id = $1 AND user_id < $2
+                AND NOT EXISTS (
+                    SELECT 1 FROM relations.blocks
+                    WHERE blocker_id = $4 AND blocks.user_id = users.id
+                )
                 ORDER BY user_id DESC
                 LIMIT $3"
             })
             .bind(id)
             .bind(before)
             .bind(limit)
+            .bind(viewer_id)
         )
     }
 
+    /// Users blocked by the viewer are excluded.
     pub async fn get_followers(
         &self,
         id: i64,
+        viewer_id: Option<i64>,
         limit: Option<u64>,
         before: Option<i64>,
     ) -> Vec<UserDto> {
@@ -186,12 +209,504 @@ impl UserRepository {
                 FROM relations.follows
                 RIGHT JOIN users ON users.id = follows.follower_id
                 WHERE user_id = $1 AND follower_id < $2
+                AND NOT EXISTS (
+                    SELECT 1 FROM relations.blocks
+                    WHERE blocker_id = $4 AND blocks.user_id = users.id
+                )
                 ORDER BY follower_id DESC
                 LIMIT $3"
             })
             .bind(id)
             .bind(before)
             .bind(limit)
+            .bind(viewer_id)
+        )
+    }
+
+    pub async fn update_password_hash(&self, id: i64, password_hash: String) -> Option<()> {
+        let result = unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query("UPDATE users SET password_hash = $2 WHERE id = $1")
+                .bind(id)
+                .bind(password_hash)
+        )?;
+
+        (result.rows_affected() > 0).then_some(())
+    }
+
+    /// Updates the profile, fields set to [`None`] are left unchanged.
+    pub async fn update_profile(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        user_id: i64,
+        bio: Option<&str>,
+        avatar_id: Option<Option<i64>>,
+        banner_id: Option<Option<i64>>,
+    ) -> Option<()> {
+        let result = unwrap_execute!(
+            &mut **tx,
+            sqlx::query(indoc! {
+                "UPDATE profiles
+                SET
+                    bio = COALESCE($2, bio),
+                    avatar_id = CASE WHEN $3 THEN $4 ELSE avatar_id END,
+                    banner_id = CASE WHEN $5 THEN $6 ELSE banner_id END
+                WHERE user_id = $1"
+            })
+            .bind(user_id)
+            .bind(bio)
+            .bind(avatar_id.is_some())
+            .bind(avatar_id.flatten())
+            .bind(banner_id.is_some())
+            .bind(banner_id.flatten())
+        )?;
+
+        (result.rows_affected() > 0).then_some(())
+    }
+
+    /// Records current username of the user into the username history.
+    pub async fn record_username(&self, tx: &mut PgTransaction<'_>, user_id: i64) -> Option<()> {
+        let result = unwrap_execute!(
+            &mut **tx,
+            sqlx::query(indoc! {
+                "INSERT INTO username_history (id, user_id, username)
+                SELECT $1, id, username FROM users WHERE id = $2"
+            })
+            .bind(snowflake())
+            .bind(user_id)
+        )?;
+
+        (result.rows_affected() > 0).then_some(())
+    }
+
+    pub async fn update_username(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        user_id: i64,
+        username: &str,
+    ) -> Option<()> {
+        let result = unwrap_execute!(
+            &mut **tx,
+            sqlx::query("UPDATE users SET username = $2 WHERE id = $1")
+                .bind(user_id)
+                .bind(username)
+        )?;
+
+        (result.rows_affected() > 0).then_some(())
+    }
+
+    /// Whether or not the username has been released by a user other than
+    /// `user_id` in the last `cooldown` seconds.
+    ///
+    /// Only the most recently released username of each user is reserved, so
+    /// that a single account cannot hold more than one name in reserve.
+    pub async fn is_username_reserved(
+        &self,
+        username: &str,
+        user_id: Option<i64>,
+        cooldown: u64,
+    ) -> Option<bool> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as::<_, (bool,)>(indoc! {
+                "SELECT EXISTS (
+                    SELECT 1 FROM username_history AS released
+                    WHERE username = $1
+                        AND user_id IS DISTINCT FROM $2
+                        AND changed_at > now() - make_interval(secs => $3)
+                        AND NOT EXISTS (
+                            SELECT 1 FROM username_history AS later
+                            WHERE later.user_id = released.user_id
+                                AND later.id > released.id
+                        )
+                )"
+            })
+            .bind(username)
+            .bind(user_id)
+            .bind(cooldown as f64)
+        )
+        .map(|row| row.0)
+    }
+
+    /// Finds the user that most recently released the username in the last
+    /// `cooldown` seconds.
+    pub async fn get_user_by_previous_username(
+        &self,
+        username: &str,
+        cooldown: u64,
+    ) -> Option<UserDto> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT
+                    users.id, users.username, flags, (
+                        SELECT avatar_id
+                        FROM profiles WHERE profiles.user_id = users.id
+                    )
+                FROM username_history
+                JOIN users ON users.id = username_history.user_id
+                WHERE username_history.username = $1
+                    AND deleted_at IS NULL
+                    AND changed_at > now() - make_interval(secs => $2)
+                ORDER BY changed_at DESC
+                LIMIT 1"
+            })
+            .bind(username)
+            .bind(cooldown as f64)
+        )
+    }
+
+    /// Sets or clears a bit of user flags, returns `false` if the bit already
+    /// has the value.
+    pub async fn set_user_flag(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        id: i64,
+        bit: usize,
+        value: bool,
+    ) -> Option<bool> {
+        unwrap_execute!(
+            &mut **tx,
+            sqlx::query(indoc! {
+                "UPDATE users SET flags = set_bit(flags, $2, $3)
+                WHERE id = $1 AND deleted_at IS NULL AND get_bit(flags, $2) <> $3"
+            })
+            .bind(id)
+            .bind(bit as i32)
+            .bind(value as i32)
+        )
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    /// Suspends the user for `duration` seconds, lifts the suspension if
+    /// [`None`].
+    pub async fn set_suspension(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        id: i64,
+        duration: Option<u64>,
+    ) -> Option<()> {
+        let result = unwrap_execute!(
+            &mut **tx,
+            sqlx::query(indoc! {
+                "UPDATE users SET suspended_until = now() + make_interval(secs => $2)
+                WHERE id = $1 AND deleted_at IS NULL"
+            })
+            .bind(id)
+            .bind(duration.map(|duration| duration as f64))
+        )?;
+
+        (result.rows_affected() > 0).then_some(())
+    }
+
+    /// Remaining seconds of the user's suspension, if suspended.
+    pub async fn get_suspension(&self, id: i64) -> Option<u64> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as::<_, (i64,)>(indoc! {
+                "SELECT CEIL(EXTRACT(epoch FROM suspended_until - now()))::bigint
+                FROM users
+                WHERE id = $1 AND suspended_until > now()"
+            })
+            .bind(id)
+        )
+        .map(|row| row.0.max(1) as u64)
+    }
+
+    /// Marks the user as deleted, removing its emails, likes and follows.
+    pub async fn soft_delete_user(&self, tx: &mut PgTransaction<'_>, id: i64) -> Option<()> {
+        let result = unwrap_execute!(
+            &mut **tx,
+            sqlx::query("UPDATE users SET deleted_at = now() WHERE id = $1 AND deleted_at IS NULL")
+                .bind(id)
+        )?;
+
+        if result.rows_affected() == 0 {
+            return None;
+        }
+
+        for query in [
+            "DELETE FROM emails WHERE user_id = $1",
+            "DELETE FROM relations.likes WHERE user_id = $1",
+            "DELETE FROM relations.follows WHERE follower_id = $1 OR user_id = $1",
+            "DELETE FROM relations.blocks WHERE blocker_id = $1 OR user_id = $1",
+            "DELETE FROM relations.mutes WHERE muter_id = $1 OR user_id = $1",
+        ] {
+            unwrap_execute!(&mut **tx, sqlx::query(query).bind(id))?;
+        }
+
+        Some(())
+    }
+
+    /// IDs of users deleted more than `grace_period` seconds ago, earliest
+    /// deleted first, excluding `skipped`.
+    pub async fn get_purgeable_user_ids(
+        &self,
+        grace_period: u64,
+        skipped: &[i64],
+        limit: i64,
+    ) -> Vec<i64> {
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as::<_, (i64,)>(indoc! {
+                "SELECT id FROM users
+                WHERE deleted_at < now() - make_interval(secs => $1)
+                    AND id != ALL($2)
+                ORDER BY deleted_at
+                LIMIT $3"
+            })
+            .bind(grace_period as f64)
+            .bind(skipped)
+            .bind(limit)
+        )
+        .into_iter()
+        .map(|row| row.0)
+        .collect()
+    }
+
+    /// Hard-deletes a soft-deleted user, releasing its username. Reports
+    /// submitted by or about the user are deleted.
+    ///
+    /// Remaining threads, posts and attachments are handed over to the
+    /// placeholder user `0`.
+    pub async fn purge_user(&self, tx: &mut PgTransaction<'_>, id: i64) -> Option<()> {
+        for query in [
+            "UPDATE posts SET user_id = 0 WHERE user_id = $1",
+            "UPDATE threads SET user_id = 0 WHERE user_id = $1",
+            "UPDATE attachments SET user_id = 0 WHERE user_id = $1",
+            "UPDATE audit_log SET actor_id = 0 WHERE actor_id = $1",
+            "DELETE FROM username_history WHERE user_id = $1",
+            "DELETE FROM reports WHERE reporter_id = $1",
+            "DELETE FROM reports WHERE target_type = 'user' AND target_id = $1",
+            "DELETE FROM profiles WHERE user_id = $1",
+        ] {
+            unwrap_execute!(&mut **tx, sqlx::query(query).bind(id))?;
+        }
+
+        let result = unwrap_execute!(
+            &mut **tx,
+            sqlx::query("DELETE FROM users WHERE id = $1 AND deleted_at IS NOT NULL").bind(id)
+        )?;
+
+        (result.rows_affected() > 0).then_some(())
+    }
+
+    /// Returns `false` if the follow relation already exists.
+    pub async fn follow_user(&self, follower_id: i64, user_id: i64) -> Option<bool> {
+        unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query(indoc! {
+                "INSERT INTO relations.follows (id, follower_id, user_id)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (follower_id, user_id) DO NOTHING"
+            })
+            .bind(snowflake())
+            .bind(follower_id)
+            .bind(user_id)
+        )
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    /// Returns `false` if the follow relation does not exist.
+    pub async fn unfollow_user(&self, follower_id: i64, user_id: i64) -> Option<bool> {
+        unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query("DELETE FROM relations.follows WHERE follower_id = $1 AND user_id = $2")
+                .bind(follower_id)
+                .bind(user_id)
+        )
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    /// Returns `false` if the block relation already exists.
+    ///
+    /// Follow relations between the users are removed in both directions.
+    pub async fn block_user(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        blocker_id: i64,
+        user_id: i64,
+    ) -> Option<bool> {
+        let result = unwrap_execute!(
+            &mut **tx,
+            sqlx::query(indoc! {
+                "INSERT INTO relations.blocks (id, blocker_id, user_id)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (blocker_id, user_id) DO NOTHING"
+            })
+            .bind(snowflake())
+            .bind(blocker_id)
+            .bind(user_id)
+        )?;
+
+        unwrap_execute!(
+            &mut **tx,
+            sqlx::query(indoc! {
+                "DELETE FROM relations.follows
+                WHERE (follower_id = $1 AND user_id = $2)
+                    OR (follower_id = $2 AND user_id = $1)"
+            })
+            .bind(blocker_id)
+            .bind(user_id)
+        )?;
+
+        Some(result.rows_affected() > 0)
+    }
+
+    /// Returns `false` if the block relation does not exist.
+    pub async fn unblock_user(&self, blocker_id: i64, user_id: i64) -> Option<bool> {
+        unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query("DELETE FROM relations.blocks WHERE blocker_id = $1 AND user_id = $2")
+                .bind(blocker_id)
+                .bind(user_id)
+        )
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    /// Returns `false` if the mute relation already exists.
+    pub async fn mute_user(&self, muter_id: i64, user_id: i64) -> Option<bool> {
+        unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query(indoc! {
+                "INSERT INTO relations.mutes (id, muter_id, user_id)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (muter_id, user_id) DO NOTHING"
+            })
+            .bind(snowflake())
+            .bind(muter_id)
+            .bind(user_id)
+        )
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    /// Returns `false` if the mute relation does not exist.
+    pub async fn unmute_user(&self, muter_id: i64, user_id: i64) -> Option<bool> {
+        unwrap_execute!(
+            &self.db.pool(),
+            sqlx::query("DELETE FROM relations.mutes WHERE muter_id = $1 AND user_id = $2")
+                .bind(muter_id)
+                .bind(user_id)
+        )
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    pub async fn is_blocking(&self, blocker_id: i64, user_id: i64) -> bool {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as::<_, (bool,)>(indoc! {
+                "SELECT EXISTS (
+                    SELECT 1 FROM relations.blocks
+                    WHERE blocker_id = $1 AND user_id = $2
+                )"
+            })
+            .bind(blocker_id)
+            .bind(user_id)
+        )
+        .is_some_and(|row| row.0)
+    }
+
+    /// Whether or not the thread is the wall of an user that blocks the user.
+    pub async fn is_blocked_from_thread(&self, thread_id: i64, user_id: i64) -> bool {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as::<_, (bool,)>(indoc! {
+                "SELECT EXISTS (
+                    SELECT 1 FROM profiles
+                    INNER JOIN relations.blocks ON blocks.blocker_id = profiles.user_id
+                    WHERE profiles.comments_thread_id = $1 AND blocks.user_id = $2
+                )"
+            })
+            .bind(thread_id)
+            .bind(user_id)
+        )
+        .is_some_and(|row| row.0)
+    }
+
+    pub async fn get_blocks(
+        &self,
+        blocker_id: i64,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<UserDto> {
+        let limit = std::cmp::min(limit.unwrap_or(32), 32) as i64;
+        let before = before.unwrap_or(i64::MAX);
+
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT
+                    user_id AS id, username, flags, (
+                        SELECT avatar_id
+                        FROM profiles
+                        WHERE profiles.user_id = users.id
+                    )
+                FROM relations.blocks
+                INNER JOIN users ON users.id = blocks.user_id
+                WHERE blocker_id = $1 AND user_id < $2
+                ORDER BY user_id DESC
+                LIMIT $3"
+            })
+            .bind(blocker_id)
+            .bind(before)
+            .bind(limit)
+        )
+    }
+
+    pub async fn get_mutes(
+        &self,
+        muter_id: i64,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<UserDto> {
+        let limit = std::cmp::min(limit.unwrap_or(32), 32) as i64;
+        let before = before.unwrap_or(i64::MAX);
+
+        unwrap_fetch_all!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT
+                    user_id AS id, username, flags, (
+                        SELECT avatar_id
+                        FROM profiles
+                        WHERE profiles.user_id = users.id
+                    )
+                FROM relations.mutes
+                INNER JOIN users ON users.id = mutes.user_id
+                WHERE muter_id = $1 AND user_id < $2
+                ORDER BY user_id DESC
+                LIMIT $3"
+            })
+            .bind(muter_id)
+            .bind(before)
+            .bind(limit)
+        )
+    }
+
+    pub async fn get_relationship(&self, caller_id: i64, user_id: i64) -> Option<RelationshipDto> {
+        unwrap_fetch_one!(
+            &self.db.pool(),
+            sqlx::query_as(indoc! {
+                "SELECT
+                    EXISTS (
+                        SELECT 1 FROM relations.follows
+                        WHERE follower_id = $1 AND user_id = $2
+                    ) AS is_following,
+                    EXISTS (
+                        SELECT 1 FROM relations.follows
+                        WHERE follower_id = $2 AND user_id = $1
+                    ) AS follows_you,
+                    EXISTS (
+                        SELECT 1 FROM relations.blocks
+                        WHERE blocker_id = $1 AND user_id = $2
+                    ) AS is_blocking,
+                    EXISTS (
+                        SELECT 1 FROM relations.mutes
+                        WHERE muter_id = $1 AND user_id = $2
+                    ) AS is_muting"
+            })
+            .bind(caller_id)
+            .bind(user_id)
         )
     }
 
@@ -243,7 +758,11 @@ impl UserRepository {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{repository::ThreadRepository, snowflake, testutil::test_db};
+    use crate::{
+        repository::{AttachmentRepository, ThreadRepository},
+        snowflake,
+        testutil::test_db,
+    };
     use serial_test::serial;
     use sqlx::types::BitVec;
 
@@ -265,6 +784,150 @@ mod tests {
         assert!(repo.__get_user_by_id(999).await.is_none());
     }
 
+    #[serial]
+    #[tokio::test]
+    async fn follows() {
+        let repo = UserRepository::new(test_db().await);
+
+        assert!(repo.follow_user(1001, 1040).await.unwrap());
+        assert!(!repo.follow_user(1001, 1040).await.unwrap());
+
+        let relationship = repo.get_relationship(1001, 1040).await.unwrap();
+        assert!(relationship.is_following && relationship.follows_you);
+
+        let relationship = repo.get_relationship(1040, 1001).await.unwrap();
+        assert!(relationship.is_following && relationship.follows_you);
+
+        assert!(repo.unfollow_user(1001, 1040).await.unwrap());
+        assert!(!repo.unfollow_user(1001, 1040).await.unwrap());
+
+        let relationship = repo.get_relationship(1001, 1040).await.unwrap();
+        assert!(!relationship.is_following && relationship.follows_you);
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn profile_update() {
+        let db = test_db().await;
+        let repo = UserRepository::new(db.clone());
+        let attachment_repo = AttachmentRepository::new(db.clone());
+
+        let attachment = entity::Attachment {
+            id: snowflake(),
+            user_id: 1006,
+            mime_type: String::from("image/png"),
+            size: 0,
+            checksum: "0".repeat(64),
+        };
+        attachment_repo
+            .create_attachment(attachment.clone())
+            .await
+            .unwrap();
+
+        let mut tx = db.pool().begin().await.unwrap();
+        repo.update_profile(
+            &mut tx,
+            1006,
+            Some("new bio"),
+            Some(Some(attachment.id)),
+            None,
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+        let profile = repo.get_profile_by_id(1006).await.unwrap();
+        assert_eq!(profile.bio, "new bio");
+        assert_eq!(profile.avatar_id, Some(attachment.id));
+        assert_eq!(profile.banner_id, None);
+
+        let mut tx = db.pool().begin().await.unwrap();
+        repo.update_profile(&mut tx, 1006, None, Some(None), None)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        let profile = repo.get_profile_by_id(1006).await.unwrap();
+        assert_eq!(profile.bio, "new bio");
+        assert_eq!(profile.avatar_id, None);
+
+        let mut tx = db.pool().begin().await.unwrap();
+        repo.update_profile(&mut tx, 1006, Some("bio for user06"), None, None)
+            .await
+            .unwrap();
+        assert!(
+            repo.update_profile(&mut tx, 999, None, None, None)
+                .await
+                .is_none()
+        );
+        tx.commit().await.unwrap();
+
+        attachment_repo
+            .delete_attachment(attachment.id)
+            .await
+            .unwrap();
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn username_change() {
+        let db = test_db().await;
+        let repo = UserRepository::new(db.clone());
+        let username = format!("{}", snowflake());
+
+        let mut tx = db.pool().begin().await.unwrap();
+        repo.record_username(&mut tx, 1007).await.unwrap();
+        repo.update_username(&mut tx, 1007, &username)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        assert_eq!(repo.get_user_by_username(&username).await.unwrap().id, 1007);
+        assert_eq!(
+            repo.get_user_by_previous_username("user07", 60)
+                .await
+                .unwrap()
+                .username,
+            username
+        );
+        assert!(repo.is_username_reserved("user07", None, 60).await.unwrap());
+        assert!(
+            !repo
+                .is_username_reserved("user07", Some(1007), 60)
+                .await
+                .unwrap()
+        );
+        assert!(!repo.is_username_reserved("user07", None, 0).await.unwrap());
+
+        let mut tx = db.pool().begin().await.unwrap();
+        repo.record_username(&mut tx, 1007).await.unwrap();
+        repo.update_username(&mut tx, 1007, &format!("{}", snowflake()))
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        assert!(!repo.is_username_reserved("user07", None, 60).await.unwrap());
+        assert!(
+            repo.is_username_reserved(&username, None, 60)
+                .await
+                .unwrap()
+        );
+
+        let mut tx = db.pool().begin().await.unwrap();
+        assert!(
+            repo.update_username(&mut tx, 1007, "user08")
+                .await
+                .is_none()
+        );
+        tx.rollback().await.unwrap();
+
+        let mut tx = db.pool().begin().await.unwrap();
+        repo.update_username(&mut tx, 1007, "user07").await.unwrap();
+        sqlx::query("DELETE FROM username_history WHERE user_id = 1007")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+    }
+
     #[serial]
     #[tokio::test]
     async fn account_creation() {
@@ -285,7 +948,7 @@ mod tests {
             entity::User {
                 id: user_id,
                 username: username.clone(),
-                flags: BitVec::from_elem(2, false),
+                flags: BitVec::from_elem(entity::USER_FLAGS_LEN, false),
             },
             password_hash.clone(),
         )
@@ -0,0 +1,302 @@
+use crate::{
+    dto::attachments::AttachmentDto,
+    entity::{self, AttachmentVariant},
+    repository::AttachmentRepository,
+    snowflake,
+    state::Database,
+    storage::Storage,
+};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// Number of attachments fetched per collection batch
+static COLLECTION_BATCH_SIZE: i64 = 128;
+
+/// Service for uploading and serving attachments.
+///
+/// Metadata of attachments is kept in the database while their content is
+/// kept in a [`Storage`] backend, keyed by attachment id. Variants of image
+/// attachments are keyed by `{id}.{variant}`.
+pub struct AttachmentService {
+    db: Database,
+    repo: AttachmentRepository,
+    storage: Arc<dyn Storage>,
+}
+
+impl AttachmentService {
+    /// Creates a new service instance.
+    pub fn new(db: Database, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            repo: AttachmentRepository::new(db.clone()),
+            db,
+            storage,
+        }
+    }
+
+    /// Stores the file with its variants and creates its attachment.
+    ///
+    /// MIME type should already be validated, and images processed, by the
+    /// caller.
+    pub async fn upload(
+        &self,
+        user_id: i64,
+        mime_type: &str,
+        data: Vec<u8>,
+        variants: Vec<(AttachmentVariant, Vec<u8>)>,
+    ) -> Option<AttachmentDto> {
+        let attachment = entity::Attachment {
+            id: snowflake(),
+            user_id,
+            mime_type: String::from(mime_type),
+            size: data.len() as i64,
+            checksum: hex::encode(Sha256::digest(&data)),
+        };
+        let key = attachment.id.to_string();
+
+        let mut stored = async {
+            self.storage.put(&key, &data).await?;
+
+            for (variant, data) in &variants {
+                self.storage
+                    .put(&variant_key(attachment.id, *variant), data)
+                    .await?;
+            }
+
+            Some(())
+        }
+        .await;
+
+        if stored.is_some() {
+            stored = self.repo.create_attachment(attachment.clone()).await;
+        }
+
+        if stored.is_none() {
+            self.delete_files(attachment.id).await;
+            return None;
+        }
+
+        Some(attachment.into())
+    }
+
+    /// Fetches metadata of the attachment.
+    pub async fn get_attachment(&self, id: i64) -> Option<entity::Attachment> {
+        self.repo.get_attachment_by_id(id).await
+    }
+
+    /// Reads content of the attachment, or one of its variants, from
+    /// storage.
+    ///
+    /// Returns `Some(None)` if the file is missing from storage.
+    pub async fn read(
+        &self,
+        id: i64,
+        variant: Option<AttachmentVariant>,
+    ) -> Option<Option<Vec<u8>>> {
+        let key = match variant {
+            Some(variant) => variant_key(id, variant),
+            None => id.to_string(),
+        };
+
+        self.storage.get(&key).await
+    }
+
+    /// Deletes the attachment with its stored files.
+    pub async fn delete_attachment(&self, id: i64) -> Option<()> {
+        self.repo.delete_attachment(id).await?;
+        self.delete_files(id).await
+    }
+
+    /// Deletes attachments that are older than the grace period and not
+    /// referenced by any post or profile, along with their stored files.
+    ///
+    /// Attachments are collected in batches until none is left. Each one is
+    /// locked before deletion, so that posts and profiles referencing it in
+    /// uncommitted transactions are seen.
+    ///
+    /// Returns count of the deleted attachments.
+    pub async fn delete_orphaned_attachments(&self, grace_period: u64) -> usize {
+        let mut deleted = 0;
+        let mut after = 0;
+
+        loop {
+            let ids = self
+                .repo
+                .get_orphaned_attachment_ids(grace_period, after, COLLECTION_BATCH_SIZE)
+                .await;
+
+            let Some(&last) = ids.last() else {
+                break;
+            };
+            after = last;
+
+            for id in ids {
+                if self.delete_attachment_if_orphaned(id).await == Some(true) {
+                    deleted += 1;
+
+                    if self.delete_files(id).await.is_none() {
+                        tracing::warn!(id, "Could not delete files of orphaned attachment");
+                    }
+                }
+            }
+        }
+
+        deleted
+    }
+
+    async fn delete_attachment_if_orphaned(&self, id: i64) -> Option<bool> {
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        self.repo.lock_attachment_for_deletion(&mut tx, id).await?;
+        let deleted = self.repo.delete_attachment_if_orphaned(&mut tx, id).await?;
+
+        tx.commit().await.ok()?;
+
+        Some(deleted)
+    }
+
+    /// Deletes the file and variants of the attachment from storage.
+    async fn delete_files(&self, id: i64) -> Option<()> {
+        let mut result = self.storage.delete(&id.to_string()).await;
+
+        for variant in AttachmentVariant::ALL {
+            result = result.and(self.storage.delete(&variant_key(id, variant)).await);
+        }
+
+        result
+    }
+}
+
+/// Storage key of the attachment variant.
+fn variant_key(id: i64, variant: AttachmentVariant) -> String {
+    format!("{id}.{}", variant.name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{service::UserService, storage::LocalStorage, testutil::test_db};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn upload() {
+        let dir = std::env::temp_dir().join(format!("metw-storage-{}", snowflake()));
+        let service = AttachmentService::new(test_db().await, Arc::new(LocalStorage::new(&dir)));
+
+        let attachment = service
+            .upload(
+                1001,
+                "image/gif",
+                b"GIF89a".to_vec(),
+                vec![(AttachmentVariant::Thumb, b"thumb".to_vec())],
+            )
+            .await
+            .unwrap();
+        assert_eq!((attachment.user_id, attachment.size), (1001, 6));
+        assert_eq!(
+            attachment.checksum,
+            "610f5ae4d76e332636a17bd357fd6ce99029316a99d320280d4d77a746bf29e8"
+        );
+
+        assert_eq!(
+            service
+                .get_attachment(attachment.id)
+                .await
+                .unwrap()
+                .mime_type,
+            "image/gif"
+        );
+        assert_eq!(
+            service.read(attachment.id, None).await.unwrap().unwrap(),
+            b"GIF89a"
+        );
+        assert_eq!(
+            service
+                .read(attachment.id, Some(AttachmentVariant::Thumb))
+                .await
+                .unwrap()
+                .unwrap(),
+            b"thumb"
+        );
+        assert!(
+            service
+                .read(attachment.id, Some(AttachmentVariant::Avatar))
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        service.delete_attachment(attachment.id).await.unwrap();
+        assert!(service.get_attachment(attachment.id).await.is_none());
+        for variant in [None, Some(AttachmentVariant::Thumb)] {
+            assert!(
+                service
+                    .read(attachment.id, variant)
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        }
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn orphaned_attachments() {
+        let db = test_db().await;
+        let dir = std::env::temp_dir().join(format!("metw-storage-{}", snowflake()));
+        let service = AttachmentService::new(db.clone(), Arc::new(LocalStorage::new(&dir)));
+        let user_service = UserService::new(db.clone());
+
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let attachment = service
+                .upload(1001, "image/gif", b"GIF89a".to_vec(), Vec::new())
+                .await
+                .unwrap();
+            ids.push(attachment.id);
+        }
+
+        let profile = user_service.get_profile_by_id(1001).await.unwrap();
+        user_service
+            .update_profile(1001, None, Some(Some(ids[0])), None)
+            .await
+            .unwrap();
+
+        // Attachments within the grace period are kept.
+        service.delete_orphaned_attachments(3600).await;
+        assert!(service.get_attachment(ids[1]).await.is_some());
+
+        // Grace period is compared in whole seconds.
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+
+        // Attachments referenced by uncommitted transactions are kept.
+        let mut tx = db.pool().begin().await.unwrap();
+        assert!(service.repo.lock_attachments(&mut tx, &[ids[2]]).await);
+        sqlx::query("UPDATE profiles SET banner_id = $1 WHERE user_id = 1001")
+            .bind(ids[2])
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+
+        let (deleted, _) = tokio::join!(service.delete_orphaned_attachments(0), async {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            tx.commit().await.unwrap();
+        });
+        assert!(deleted > 0);
+        assert!(service.get_attachment(ids[0]).await.is_some());
+        assert!(service.get_attachment(ids[1]).await.is_none());
+        assert!(service.get_attachment(ids[2]).await.is_some());
+        assert!(service.read(ids[1], None).await.unwrap().is_none());
+
+        user_service
+            .update_profile(1001, None, Some(profile.avatar_id), Some(profile.banner_id))
+            .await
+            .unwrap();
+        service.delete_attachment(ids[0]).await.unwrap();
+        service.delete_attachment(ids[2]).await.unwrap();
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{
+    dto::emails::EmailDto,
+    entity,
+    mailer::{Mail, Mailer},
+    repository::EmailRepository,
+    service::token_service::{
+        EmailVerificationToken, TokenService, new_email_verification_token_service,
+    },
+    snowflake,
+    state::{Database, Redis},
+};
+use std::sync::Arc;
+
+/// Service struct for handling email addresses of accounts and their
+/// verification.
+pub struct EmailService {
+    repo: EmailRepository,
+    mailer: Arc<dyn Mailer>,
+    token_service: TokenService<EmailVerificationToken>,
+}
+
+impl EmailService {
+    /// Creates a new repository instance.
+    pub fn new(db: Database, redis: Redis, mailer: Arc<dyn Mailer>, secret: &str) -> Self {
+        Self {
+            repo: EmailRepository::new(db),
+            token_service: new_email_verification_token_service(redis, secret),
+            mailer,
+        }
+    }
+
+    /// Finds an email from its ID.
+    pub async fn get_email_by_id(&self, id: i64) -> Option<entity::Email> {
+        self.repo.get_email_by_id(id).await
+    }
+
+    /// Lists emails of the user.
+    pub async fn get_emails_of_user(&self, user_id: i64) -> Vec<EmailDto> {
+        self.repo
+            .get_emails_of_user(user_id)
+            .await
+            .into_iter()
+            .map(|email| email.into())
+            .collect()
+    }
+
+    /// Counts emails of the user.
+    pub async fn count_emails_of_user(&self, user_id: i64) -> Option<i64> {
+        self.repo.count_emails_of_user(user_id).await
+    }
+
+    /// Adds an unverified email to the account and mails a verification
+    /// token to it.
+    ///
+    /// Addresses verified by another account can be added as well, but they
+    /// are not mailed and cannot be verified, so that the response does not
+    /// reveal whether an address is registered.
+    ///
+    /// Returns [`None`] if the user has already added the email.
+    pub async fn add_email(&self, user_id: i64, email: String) -> Option<EmailDto> {
+        let email = entity::Email {
+            id: snowflake(),
+            user_id,
+            email,
+            is_verified: false,
+        };
+
+        if !self.repo.create_email(email.clone()).await? {
+            return None;
+        }
+
+        self.send_verification(&email).await;
+
+        Some(email.into())
+    }
+
+    /// Mails a new verification token to the email.
+    ///
+    /// Nothing is mailed if the email has been verified by another account.
+    pub async fn send_verification(&self, email: &entity::Email) -> Option<()> {
+        if self
+            .repo
+            .get_verified_email(&email.email)
+            .await
+            .is_some_and(|verified| verified.id != email.id)
+        {
+            return Some(());
+        }
+
+        let token = self
+            .token_service
+            .sign(EmailVerificationToken::new(
+                email.user_id,
+                email.id,
+                email.email.clone(),
+            ))
+            .await;
+
+        self.mailer
+            .send(&Mail {
+                to: email.email.clone(),
+                subject: String::from("Verify your email address"),
+                body: format!("Use the following token to verify your email address:\n\n{token}"),
+            })
+            .await
+    }
+
+    /// Verifies the email that token was issued for.
+    ///
+    /// Tokens are single-use, and are rejected if the email has been removed
+    /// or verified by another account since. Verifying an email removes its
+    /// unverified claims of other accounts.
+    pub async fn verify_email(&self, jwt: &str) -> Option<EmailDto> {
+        let token = self.token_service.validate(jwt).await?;
+
+        let email = self
+            .repo
+            .verify_email(token.email_id, token.id, &token.email)
+            .await?;
+
+        self.token_service.revoke_token(&token).await;
+
+        Some(email.into())
+    }
+
+    /// Removes the email from the account.
+    ///
+    /// Returns `false` if the user has no such email.
+    pub async fn delete_email(&self, id: i64, user_id: i64) -> Option<bool> {
+        self.repo.delete_email(id, user_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mailer::FileMailer,
+        testutil::{test_db, test_redis},
+    };
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn verification() {
+        let dir = std::env::temp_dir().join(format!("metw-mails-{}", snowflake()));
+        let service = EmailService::new(
+            test_db().await,
+            test_redis().await,
+            Arc::new(FileMailer::new(&dir)),
+            "jwt-secret",
+        );
+
+        let address = format!("{}@example.com", snowflake());
+        let email = service.add_email(1003, address.clone()).await.unwrap();
+        assert!(!email.is_verified);
+        assert!(service.add_email(1003, address.clone()).await.is_none());
+        let claim = service.add_email(1004, address.clone()).await.unwrap();
+
+        let mut mails: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|mail| mail.unwrap().path())
+            .collect();
+        mails.sort();
+        let tokens: Vec<_> = mails
+            .iter()
+            .map(|mail| {
+                let mail = std::fs::read_to_string(mail).unwrap();
+                mail.trim().lines().last().unwrap().to_string()
+            })
+            .collect();
+
+        assert!(service.verify_email(&tokens[0]).await.unwrap().is_verified);
+        assert!(service.verify_email(&tokens[0]).await.is_none());
+
+        assert!(service.get_email_by_id(claim.id).await.is_none());
+        assert!(service.verify_email(&tokens[1]).await.is_none());
+
+        // verified addresses can be added, but are not mailed
+        let claim = service.add_email(1004, address).await.unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), tokens.len());
+        assert!(service.delete_email(claim.id, 1004).await.unwrap());
+
+        assert!(service.delete_email(email.id, 1003).await.unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
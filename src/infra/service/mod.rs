@@ -5,5 +5,23 @@ mod user_service;
 
 mod post_service;
 
+mod email_service;
+
+mod password_reset_service;
+
+mod rate_limit_service;
+
+mod moderation_service;
+
+mod report_service;
+
+mod attachment_service;
+
+pub use attachment_service::AttachmentService;
+pub use email_service::EmailService;
+pub use moderation_service::ModerationService;
+pub use password_reset_service::PasswordResetService;
 pub use post_service::PostService;
+pub use rate_limit_service::{RateLimit, RateLimitService};
+pub use report_service::ReportService;
 pub use user_service::UserService;
@@ -0,0 +1,325 @@
+use crate::{
+    dto::moderation::AuditEntryDto,
+    entity,
+    repository::{AuditRepository, PostRepository, ThreadRepository, UserRepository},
+    snowflake,
+    state::{Database, Redis},
+};
+use redis::AsyncCommands;
+use sqlx::PgTransaction;
+
+/// Seconds a user that is not suspended is cached for
+static SUSPENSION_CACHE_TTL: u64 = 300;
+
+/// Service for moderator actions on posts and users.
+///
+/// Every action is recorded in the audit log. Suspensions are stored in the
+/// database and mirrored to Redis, so that authenticated requests can be
+/// checked without a database query. Users missing in Redis are read from the
+/// database and cached, including ones that are not suspended.
+pub struct ModerationService {
+    db: Database,
+    redis: Redis,
+    repo: AuditRepository,
+    post_repo: PostRepository,
+    thread_repo: ThreadRepository,
+    user_repo: UserRepository,
+}
+
+impl ModerationService {
+    /// Creates a new service instance.
+    pub fn new(db: Database, redis: Redis) -> Self {
+        Self {
+            repo: AuditRepository::new(db.clone()),
+            post_repo: PostRepository::new(db.clone()),
+            thread_repo: ThreadRepository::new(db.clone()),
+            user_repo: UserRepository::new(db.clone()),
+            db,
+            redis,
+        }
+    }
+
+    fn suspension_key(user_id: i64) -> String {
+        format!("suspension:{user_id}")
+    }
+
+    async fn audit(
+        &self,
+        tx: &mut PgTransaction<'_>,
+        actor_id: i64,
+        action: &str,
+        target_id: i64,
+        details: String,
+    ) -> Option<()> {
+        self.repo
+            .create_audit_entry(
+                tx,
+                entity::AuditEntry {
+                    id: snowflake(),
+                    actor_id,
+                    action: String::from(action),
+                    target_id,
+                    details: Some(details),
+                },
+            )
+            .await
+    }
+
+    /// Hides or unhides the post.
+    ///
+    /// Returns `false` if the post already has the visibility.
+    pub async fn set_post_hidden(
+        &self,
+        actor_id: i64,
+        post_id: i64,
+        hidden: bool,
+        reason: String,
+    ) -> Option<bool> {
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        if !self
+            .post_repo
+            .set_post_hidden(&mut tx, post_id, hidden)
+            .await?
+        {
+            return Some(false);
+        }
+
+        let action = if hidden { "hide_post" } else { "unhide_post" };
+        self.audit(&mut tx, actor_id, action, post_id, reason)
+            .await?;
+
+        tx.commit().await.ok()?;
+
+        Some(true)
+    }
+
+    /// Fetches the post, even if hidden.
+    pub async fn get_post(&self, post_id: i64) -> Option<entity::Post> {
+        self.post_repo.get_post_including_hidden(post_id).await
+    }
+
+    /// Removes the post with its likes.
+    pub async fn remove_post(&self, actor_id: i64, post_id: i64, reason: String) -> Option<()> {
+        let post = self.post_repo.get_post_including_hidden(post_id).await?;
+
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        self.post_repo.delete_post(&mut tx, post_id).await?;
+        self.thread_repo
+            .delete_thread_if_empty(&mut tx, post.replies_thread_id)
+            .await?;
+        self.audit(&mut tx, actor_id, "remove_post", post_id, reason)
+            .await?;
+
+        tx.commit().await.ok()
+    }
+
+    /// Suspends the user for `duration` seconds.
+    pub async fn suspend_user(
+        &self,
+        actor_id: i64,
+        user_id: i64,
+        duration: u64,
+        reason: String,
+    ) -> Option<()> {
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        self.user_repo
+            .set_suspension(&mut tx, user_id, Some(duration))
+            .await?;
+        self.audit(
+            &mut tx,
+            actor_id,
+            "suspend_user",
+            user_id,
+            format!("{reason} ({duration} seconds)"),
+        )
+        .await?;
+
+        tx.commit().await.ok()?;
+
+        self.redis
+            .client()
+            .set_ex::<_, _, ()>(Self::suspension_key(user_id), 1, duration)
+            .await
+            .ok()
+    }
+
+    /// Lifts the user's suspension.
+    pub async fn unsuspend_user(&self, actor_id: i64, user_id: i64, reason: String) -> Option<()> {
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        self.user_repo
+            .set_suspension(&mut tx, user_id, None)
+            .await?;
+        self.audit(&mut tx, actor_id, "unsuspend_user", user_id, reason)
+            .await?;
+
+        tx.commit().await.ok()?;
+
+        self.redis
+            .client()
+            .del::<_, ()>(Self::suspension_key(user_id))
+            .await
+            .ok()
+    }
+
+    /// Remaining seconds of the user's suspension, read from the database.
+    pub async fn get_suspension(&self, user_id: i64) -> Option<u64> {
+        self.user_repo.get_suspension(user_id).await
+    }
+
+    /// Remaining seconds of the user's suspension, read from Redis.
+    ///
+    /// The key holds `1` for suspended users, expiring with the suspension,
+    /// and `0` for users that are not suspended. Falls back to the database
+    /// if the user is not cached or Redis is unavailable.
+    pub async fn get_cached_suspension(&self, user_id: i64) -> Option<u64> {
+        let key = Self::suspension_key(user_id);
+
+        let cached: Result<(Option<i64>, i64), _> = redis::pipe()
+            .get(&key)
+            .ttl(&key)
+            .query_async(&mut self.redis.client())
+            .await;
+
+        match cached {
+            Ok((Some(0), _)) => None,
+            Ok((Some(_), ttl)) if ttl > 0 => Some(ttl as u64),
+            Ok(_) => {
+                let remaining = self.get_suspension(user_id).await;
+
+                // NX, so that a suspension set meanwhile is not overwritten
+                let _: Result<(), _> = redis::cmd("SET")
+                    .arg(&key)
+                    .arg(remaining.is_some() as i64)
+                    .arg("EX")
+                    .arg(remaining.unwrap_or(SUSPENSION_CACHE_TTL))
+                    .arg("NX")
+                    .query_async(&mut self.redis.client())
+                    .await;
+
+                remaining
+            }
+            Err(_) => self.get_suspension(user_id).await,
+        }
+    }
+
+    /// Fetches the audit log, latest entries first.
+    pub async fn get_audit_log(
+        &self,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<AuditEntryDto> {
+        self.repo
+            .get_audit_entries(limit, before)
+            .await
+            .into_iter()
+            .map(AuditEntryDto::from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entity::{ReportReason, ReportTarget},
+        service::{PostService, ReportService},
+        testutil::{test_db, test_redis},
+    };
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn moderation() {
+        let db = test_db().await;
+        let service = ModerationService::new(db.clone(), test_redis().await);
+        let post_service = PostService::new(db.clone());
+        let report_service = ReportService::new(db);
+
+        let post = post_service
+            .create_post(1005, Some(2001), String::from("post"), vec![])
+            .await
+            .unwrap();
+
+        assert!(
+            service
+                .set_post_hidden(1001, post.id, true, String::from("spam"))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !service
+                .set_post_hidden(1001, post.id, true, String::from("spam"))
+                .await
+                .unwrap()
+        );
+        assert!(post_service.get_post_by_id(post.id).await.is_none());
+        assert!(service.get_post(post.id).await.is_some());
+
+        let entry = service.get_audit_log(Some(1), None).await.remove(0);
+        assert_eq!(
+            (entry.action.as_str(), entry.target_id),
+            ("hide_post", post.id)
+        );
+
+        report_service
+            .report(1006, ReportTarget::Post, post.id, ReportReason::Spam, None)
+            .await
+            .unwrap();
+
+        service
+            .remove_post(1001, post.id, String::from("spam"))
+            .await
+            .unwrap();
+        assert!(service.get_post(post.id).await.is_none());
+        assert!(
+            report_service
+                .get_reports_of_target(ReportTarget::Post, post.id)
+                .await
+                .is_empty()
+        );
+
+        let log = service.get_audit_log(Some(2), None).await;
+        assert_eq!(log[0].action, "remove_post");
+        assert_eq!(
+            service.get_audit_log(Some(1), Some(log[0].id)).await[0].id,
+            log[1].id
+        );
+
+        service
+            .suspend_user(1001, 1005, 60, String::from("spam"))
+            .await
+            .unwrap();
+        assert!((1..=60).contains(&service.get_suspension(1005).await.unwrap()));
+        assert!((1..=60).contains(&service.get_cached_suspension(1005).await.unwrap()));
+
+        // suspension is read from the database on Redis miss
+        let key = ModerationService::suspension_key(1005);
+        let mut redis = service.redis.client();
+        redis.del::<_, ()>(&key).await.unwrap();
+        assert!((1..=60).contains(&service.get_cached_suspension(1005).await.unwrap()));
+        assert_eq!(redis.get::<_, i64>(&key).await.unwrap(), 1);
+
+        service
+            .unsuspend_user(1001, 1005, String::from("appeal"))
+            .await
+            .unwrap();
+        assert!(service.get_suspension(1005).await.is_none());
+        assert!(service.get_cached_suspension(1005).await.is_none());
+        assert_eq!(redis.get::<_, i64>(&key).await.unwrap(), 0);
+
+        service
+            .suspend_user(1001, 1005, 60, String::from("spam"))
+            .await
+            .unwrap();
+        assert!(service.get_cached_suspension(1005).await.is_some());
+
+        service
+            .unsuspend_user(1001, 1005, String::from("appeal"))
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    mailer::{Mail, Mailer},
+    repository::EmailRepository,
+    state::{Database, Redis},
+    util::random_token,
+};
+use redis::AsyncCommands;
+use std::sync::Arc;
+
+/// 1 hour
+static PASSWORD_RESET_TOKEN_TTL: u64 = 3600;
+
+/// Service for recovering accounts through their verified emails.
+///
+/// Reset tokens are random, single-use strings stored in Redis.
+pub struct PasswordResetService {
+    redis: Redis,
+    email_repo: EmailRepository,
+    mailer: Arc<dyn Mailer>,
+}
+
+impl PasswordResetService {
+    /// Creates a new repository instance.
+    pub fn new(db: Database, redis: Redis, mailer: Arc<dyn Mailer>) -> Self {
+        Self {
+            redis,
+            email_repo: EmailRepository::new(db),
+            mailer,
+        }
+    }
+
+    fn key(token: &str) -> String {
+        format!("password-reset:{token}")
+    }
+
+    /// Mails a reset token to the email if it is verified.
+    ///
+    /// Does nothing for unknown or unverified emails.
+    pub async fn request_reset(&self, email: &str) -> Option<()> {
+        let Some(email) = self.email_repo.get_verified_email(email).await else {
+            return Some(());
+        };
+
+        let token = random_token();
+
+        self.redis
+            .client()
+            .set_ex::<_, _, ()>(Self::key(&token), email.user_id, PASSWORD_RESET_TOKEN_TTL)
+            .await
+            .ok()?;
+
+        self.mailer
+            .send(&Mail {
+                to: email.email,
+                subject: String::from("Reset your password"),
+                body: format!(
+                    "Use the following token to reset your password within an hour:\n\n{token}"
+                ),
+            })
+            .await
+    }
+
+    /// Consumes the reset token, returning the user ID it was issued for.
+    pub async fn consume_token(&self, token: &str) -> Option<i64> {
+        self.redis
+            .client()
+            .get_del::<_, Option<i64>>(Self::key(token))
+            .await
+            .ok()?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entity,
+        mailer::FileMailer,
+        snowflake,
+        testutil::{test_db, test_redis},
+    };
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn reset_token() {
+        let db = test_db().await;
+        let dir = std::env::temp_dir().join(format!("metw-mails-{}", snowflake()));
+        let service = PasswordResetService::new(
+            db.clone(),
+            test_redis().await,
+            Arc::new(FileMailer::new(&dir)),
+        );
+        let email_repo = EmailRepository::new(db);
+
+        let address = format!("{}@example.com", snowflake());
+        let email = entity::Email {
+            id: snowflake(),
+            user_id: 1005,
+            email: address.clone(),
+            is_verified: false,
+        };
+        email_repo.create_email(email.clone()).await.unwrap();
+
+        service.request_reset(&address).await.unwrap();
+        assert!(!dir.exists());
+
+        email_repo
+            .verify_email(email.id, email.user_id, &address)
+            .await
+            .unwrap();
+        service.request_reset(&address).await.unwrap();
+
+        let mut mails = std::fs::read_dir(&dir).unwrap();
+        let mail = std::fs::read_to_string(mails.next().unwrap().unwrap().path()).unwrap();
+        let token = mail.trim().lines().last().unwrap();
+
+        assert_eq!(service.consume_token(token).await, Some(1005));
+        assert_eq!(service.consume_token(token).await, None);
+
+        email_repo
+            .delete_email(email.id, email.user_id)
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,13 +1,21 @@
 use crate::{
-    dto::posts::{PostDto, PostStatsDto},
-    repository::{PostRepository, ThreadRepository},
+    dto::{
+        posts::{PostContextDto, PostDto, PostStatsDto, ReplyTreeDto},
+        user::UserDto,
+    },
+    entity,
+    repository::{AttachmentRepository, PostRepository, ThreadRepository},
+    snowflake,
     state::Database,
 };
+use std::collections::HashMap;
 
 /// Service struct for handling post-related operations.
 pub struct PostService {
+    db: Database,
     repo: PostRepository,
     thread_repo: ThreadRepository,
+    attachment_repo: AttachmentRepository,
 }
 
 impl PostService {
@@ -15,7 +23,9 @@ impl PostService {
     pub fn new(db: Database) -> Self {
         Self {
             repo: PostRepository::new(db.clone()),
-            thread_repo: ThreadRepository::new(db),
+            thread_repo: ThreadRepository::new(db.clone()),
+            attachment_repo: AttachmentRepository::new(db.clone()),
+            db,
         }
     }
 
@@ -29,32 +39,430 @@ impl PostService {
         self.repo.get_post_stats_by_id(id).await
     }
 
-    /// Gets the latest posts in a thread.
+    /// Checks whether the thread exists.
+    pub async fn thread_exists(&self, thread_id: i64) -> bool {
+        self.thread_repo.get_thread_by_id(thread_id).await.is_some()
+    }
+
+    /// Creates a post with its replies thread.
+    ///
+    /// Returns the created post if succeedded, [`None`] if any of the
+    /// attachments does not exist.
+    pub async fn create_post(
+        &self,
+        user_id: i64,
+        thread_id: Option<i64>,
+        content: String,
+        attachments: Vec<i64>,
+    ) -> Option<PostDto> {
+        let post = entity::Post {
+            id: snowflake(),
+            user_id,
+            thread_id,
+            replies_thread_id: snowflake(),
+            is_edited: false,
+            content,
+            attachments,
+        };
+
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        if !self
+            .attachment_repo
+            .lock_attachments(&mut tx, &post.attachments)
+            .await
+        {
+            return None;
+        }
+
+        self.thread_repo
+            .create_thread(
+                &mut tx,
+                entity::Thread {
+                    id: post.replies_thread_id,
+                    user_id,
+                },
+            )
+            .await?;
+
+        self.repo.create_post(&mut tx, post.clone()).await?;
+
+        tx.commit().await.ok()?;
+
+        Some(post.into())
+    }
+
+    /// Updates content and attachments of a post, marking it as edited.
+    ///
+    /// Returns [`None`] if any of the attachments does not exist.
+    pub async fn edit_post(&self, id: i64, content: &str, attachments: &[i64]) -> Option<PostDto> {
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        if !self
+            .attachment_repo
+            .lock_attachments(&mut tx, attachments)
+            .await
+        {
+            return None;
+        }
+
+        let post = self
+            .repo
+            .update_post(&mut tx, id, content, attachments)
+            .await?;
+
+        tx.commit().await.ok()?;
+
+        Some(post.into())
+    }
+
+    /// Deletes a post, and its replies thread if nobody replied to it.
+    pub async fn delete_post(&self, id: i64) -> Option<()> {
+        let post = self.repo.get_post_by_id(id).await?;
+
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        self.repo.delete_post(&mut tx, id).await?;
+        self.thread_repo
+            .delete_thread_if_empty(&mut tx, post.replies_thread_id)
+            .await?;
+
+        tx.commit().await.ok()
+    }
+
+    /// Likes a post on behalf of the user.
+    pub async fn like_post(&self, user_id: i64, post_id: i64) -> Option<()> {
+        self.repo.like_post(user_id, post_id).await
+    }
+
+    /// Removes the user's like from a post.
+    pub async fn unlike_post(&self, user_id: i64, post_id: i64) -> Option<()> {
+        self.repo.unlike_post(user_id, post_id).await
+    }
+
+    /// Fetches users that liked the post.
+    pub async fn get_likes(
+        &self,
+        post_id: i64,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<UserDto> {
+        self.repo.get_likes(post_id, limit, before).await
+    }
+
+    /// Gets the latest posts in a thread, as seen by the viewer.
     pub async fn get_latest_posts_of_thread(
         &self,
         thread_id: Option<i64>,
+        viewer_id: Option<i64>,
         limit: Option<u64>,
         before: Option<i64>,
     ) -> Vec<PostDto> {
         self.thread_repo
-            .get_latest_posts(thread_id, limit, before)
+            .get_latest_posts(thread_id, viewer_id, limit, before)
             .await
             .into_iter()
             .map(|post| post.into())
             .collect()
     }
 
-    /// Gets the hot posts in a thread.
+    /// Gets the latest posts of the users that the follower follows.
+    pub async fn get_timeline(
+        &self,
+        follower_id: i64,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<PostDto> {
+        self.thread_repo
+            .get_timeline_posts(follower_id, limit, before)
+            .await
+            .into_iter()
+            .map(|post| post.into())
+            .collect()
+    }
+
+    /// Gets the hot posts in a thread, as seen by the viewer.
     pub async fn get_hot_posts_of_thread(
         &self,
         thread_id: Option<i64>,
+        viewer_id: Option<i64>,
         time_period: Option<u64>,
     ) -> Vec<PostDto> {
         self.thread_repo
-            .get_hot_posts(thread_id, time_period)
+            .get_hot_posts(thread_id, viewer_id, time_period)
             .await
             .into_iter()
             .map(|post| post.into())
             .collect()
     }
+
+    /// Gets the latest replies in a thread as a tree, as seen by the viewer.
+    pub async fn get_reply_tree(
+        &self,
+        thread_id: i64,
+        viewer_id: Option<i64>,
+        depth: Option<u64>,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<ReplyTreeDto> {
+        let mut replies: HashMap<i64, Vec<entity::Reply>> = HashMap::new();
+
+        for reply in self
+            .thread_repo
+            .get_reply_tree(thread_id, viewer_id, depth, limit, before)
+            .await
+        {
+            if let Some(thread_id) = reply.post.thread_id {
+                replies.entry(thread_id).or_default().push(reply);
+            }
+        }
+
+        build_reply_tree(&mut replies, thread_id)
+    }
+
+    /// Gets ancestors of the post up to its root, and the thread that the
+    /// root belongs to.
+    ///
+    /// If the root thread is neither the main thread nor a profile wall, the
+    /// post that the root replies to has been deleted.
+    ///
+    /// Only the closest ancestors are returned for deeply nested posts, the
+    /// root thread is not known in that case.
+    pub async fn get_post_context(&self, post: &PostDto) -> PostContextDto {
+        let (ancestors, truncated) = self.thread_repo.get_ancestors(post.id).await;
+
+        let root_thread_id = if truncated {
+            None
+        } else {
+            ancestors
+                .first()
+                .map_or(post.thread_id, |ancestor| ancestor.post.thread_id)
+        };
+        let wall_user_id = match root_thread_id {
+            Some(thread_id) => self.thread_repo.get_wall_owner_id(thread_id).await,
+            None => None,
+        };
+
+        PostContextDto {
+            ancestors: ancestors
+                .into_iter()
+                .filter(|ancestor| !ancestor.is_hidden)
+                .map(|ancestor| ancestor.post.into())
+                .collect(),
+            root_thread_id,
+            wall_user_id,
+            truncated,
+        }
+    }
+}
+
+/// Builds reply trees of the thread from replies grouped by their thread.
+fn build_reply_tree(
+    replies: &mut HashMap<i64, Vec<entity::Reply>>,
+    thread_id: i64,
+) -> Vec<ReplyTreeDto> {
+    replies
+        .remove(&thread_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|reply| {
+            let children = build_reply_tree(replies, reply.post.replies_thread_id);
+            let next_before = children
+                .last()
+                .filter(|_| reply.has_more_replies)
+                .map(|child| child.post.id);
+
+            ReplyTreeDto {
+                post: reply.post.into(),
+                replies: children,
+                has_more_replies: reply.has_more_replies,
+                next_before,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::test_db;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn post_lifecycle() {
+        let service = PostService::new(test_db().await);
+
+        let content = format!("{}", snowflake());
+        let post = service
+            .create_post(1001, Some(2002), content.clone(), vec![])
+            .await
+            .unwrap();
+
+        assert!(service.thread_exists(post.replies_thread_id).await);
+        assert_eq!(
+            service.get_post_by_id(post.id).await.unwrap().content,
+            content
+        );
+
+        assert!(service.edit_post(post.id, "edited", &[1]).await.is_none());
+
+        let edited = service.edit_post(post.id, "edited", &[]).await.unwrap();
+        assert!(edited.is_edited);
+        assert_eq!(edited.content, "edited");
+
+        service.delete_post(post.id).await.unwrap();
+
+        assert!(service.get_post_by_id(post.id).await.is_none());
+        assert!(!service.thread_exists(post.replies_thread_id).await);
+        assert!(service.delete_post(post.id).await.is_none());
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn reply_tree() {
+        let service = PostService::new(test_db().await);
+
+        let reply = async |thread_id| {
+            service
+                .create_post(1001, Some(thread_id), String::from("reply"), vec![])
+                .await
+                .unwrap()
+        };
+
+        let root = reply(2002).await;
+        let mut replies = Vec::new();
+        for _ in 0..3 {
+            replies.push(reply(root.replies_thread_id).await);
+        }
+        let mut children = Vec::new();
+        for _ in 0..5 {
+            children.push(reply(replies[2].replies_thread_id).await);
+        }
+        let grandchild = reply(children[4].replies_thread_id).await;
+
+        let tree = service
+            .get_reply_tree(root.replies_thread_id, None, None, Some(2), None)
+            .await;
+        let ids: Vec<_> = tree.iter().map(|node| node.post.id).collect();
+        assert_eq!(ids, [replies[2].id, replies[1].id]);
+
+        // Replies below the first level are limited to 4 per post.
+        let ids: Vec<_> = tree[0].replies.iter().map(|node| node.post.id).collect();
+        assert_eq!(
+            ids,
+            [
+                children[4].id,
+                children[3].id,
+                children[2].id,
+                children[1].id
+            ]
+        );
+        assert!(tree[0].has_more_replies);
+        assert_eq!(tree[0].next_before, Some(children[1].id));
+
+        // Grandchild is beyond the default depth.
+        assert!(tree[0].replies[0].replies.is_empty());
+        assert!(tree[0].replies[0].has_more_replies);
+        assert_eq!(tree[0].replies[0].next_before, None);
+        assert!(!tree[1].has_more_replies);
+
+        let tree = service
+            .get_reply_tree(
+                root.replies_thread_id,
+                None,
+                Some(3),
+                None,
+                Some(replies[1].id),
+            )
+            .await;
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].post.id, replies[0].id);
+
+        let tree = service
+            .get_reply_tree(root.replies_thread_id, None, Some(3), None, None)
+            .await;
+        assert_eq!(tree[0].replies[0].replies[0].post.id, grandchild.id);
+        assert!(!tree[0].replies[0].has_more_replies);
+
+        service.delete_post(grandchild.id).await.unwrap();
+        for post in children.iter().chain(replies.iter()).chain([&root]) {
+            service.delete_post(post.id).await.unwrap();
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn post_context() {
+        let service = PostService::new(test_db().await);
+
+        let post = async |thread_id| {
+            service
+                .create_post(1001, thread_id, String::from("reply"), vec![])
+                .await
+                .unwrap()
+        };
+
+        // Thread 2002 is wall of user02.
+        let mut chain = vec![post(Some(2002)).await];
+        for _ in 0..3 {
+            chain.push(post(Some(chain.last().unwrap().replies_thread_id)).await);
+        }
+
+        let context = service.get_post_context(&chain[3]).await;
+        let ids: Vec<_> = context.ancestors.iter().map(|post| post.id).collect();
+        assert_eq!(ids, [chain[0].id, chain[1].id, chain[2].id]);
+        assert_eq!(
+            (context.root_thread_id, context.wall_user_id),
+            (Some(2002), Some(1002))
+        );
+        assert!(!context.truncated);
+
+        let context = service.get_post_context(&chain[0]).await;
+        assert!(context.ancestors.is_empty());
+        assert_eq!(context.root_thread_id, Some(2002));
+
+        // Replies of deleted posts are detached from ancestors above.
+        service.delete_post(chain[1].id).await.unwrap();
+        let context = service.get_post_context(&chain[3]).await;
+        let ids: Vec<_> = context.ancestors.iter().map(|post| post.id).collect();
+        assert_eq!(ids, [chain[2].id]);
+        assert_eq!(
+            (context.root_thread_id, context.wall_user_id),
+            (Some(chain[1].replies_thread_id), None)
+        );
+
+        for post in [&chain[3], &chain[2], &chain[0]] {
+            service.delete_post(post.id).await.unwrap();
+        }
+        sqlx::query("DELETE FROM threads WHERE id = $1")
+            .bind(chain[1].replies_thread_id)
+            .execute(&service.db.pool())
+            .await
+            .unwrap();
+
+        // Main thread posts have no root thread.
+        let mut chain = vec![post(None).await];
+        chain.push(post(Some(chain[0].replies_thread_id)).await);
+
+        let context = service.get_post_context(&chain[1]).await;
+        assert_eq!(context.ancestors[0].id, chain[0].id);
+        assert_eq!((context.root_thread_id, context.wall_user_id), (None, None));
+        assert!(!context.truncated);
+
+        // Only the closest 256 ancestors are fetched.
+        for _ in 0..256 {
+            chain.push(post(Some(chain.last().unwrap().replies_thread_id)).await);
+        }
+
+        let context = service.get_post_context(chain.last().unwrap()).await;
+        assert_eq!(context.ancestors.len(), 256);
+        assert_eq!(context.ancestors[0].id, chain[1].id);
+        assert_eq!(context.root_thread_id, None);
+        assert!(context.truncated);
+
+        for post in chain.iter().rev() {
+            service.delete_post(post.id).await.unwrap();
+        }
+    }
 }
@@ -0,0 +1,139 @@
+use crate::{
+    snowflake,
+    state::{RateLimitRule, Redis},
+};
+use chrono::Utc;
+use redis::AsyncCommands;
+
+/// Outcome of a rate limited request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Maximum request count in a window
+    pub limit: u64,
+    /// Requests left in the current window
+    pub remaining: u64,
+    /// Seconds until the oldest request in the window expires
+    pub reset: u64,
+    /// Seconds to wait before retrying, set if the request is rejected
+    pub retry_after: Option<u64>,
+}
+
+impl RateLimit {
+    /// Whether or not the request is allowed.
+    pub fn is_allowed(&self) -> bool {
+        self.retry_after.is_none()
+    }
+}
+
+/// Sliding window rate limiter.
+///
+/// Each counter is a Redis sorted set of request timestamps. Rejected
+/// requests are not recorded, so clients are not locked out for longer than
+/// the window.
+pub struct RateLimitService {
+    redis: Redis,
+}
+
+impl RateLimitService {
+    /// Creates a new service instance.
+    pub fn new(redis: Redis) -> Self {
+        Self { redis }
+    }
+
+    fn key(scope: &str, id: &str) -> String {
+        format!("rate-limit:{scope}:{id}")
+    }
+
+    /// Records a request for `id` in the `scope`, returning whether it is
+    /// allowed by the rule.
+    ///
+    /// Returns [`None`] if Redis is unavailable, callers should not reject
+    /// requests in that case.
+    pub async fn hit(&self, scope: &str, id: &str, rule: RateLimitRule) -> Option<RateLimit> {
+        let key = Self::key(scope, id);
+        let member = snowflake().to_string();
+        let now = Utc::now().timestamp_millis();
+        let window = rule.window as i64 * 1000;
+
+        let (count, oldest): (u64, Vec<(String, f64)>) = redis::pipe()
+            .atomic()
+            .zrembyscore(&key, "-inf", now - window)
+            .ignore()
+            .zadd(&key, &member, now)
+            .ignore()
+            .zcard(&key)
+            .zrange_withscores(&key, 0, 0)
+            .pexpire(&key, window)
+            .ignore()
+            .query_async(&mut self.redis.client())
+            .await
+            .ok()?;
+
+        let oldest = oldest.first().map_or(now, |(_, score)| *score as i64);
+        let reset = ((oldest + window - now).max(0) as u64).div_ceil(1000);
+
+        if count > rule.limit {
+            self.redis
+                .client()
+                .zrem::<_, _, ()>(&key, &member)
+                .await
+                .ok()?;
+
+            Some(RateLimit {
+                limit: rule.limit,
+                remaining: 0,
+                reset,
+                retry_after: Some(reset.max(1)),
+            })
+        } else {
+            Some(RateLimit {
+                limit: rule.limit,
+                remaining: rule.limit - count,
+                reset,
+                retry_after: None,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::test_redis;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn sliding_window() {
+        let service = RateLimitService::new(test_redis().await);
+        let id = snowflake().to_string();
+        let rule = RateLimitRule::new(3, 60);
+
+        for remaining in (0..3).rev() {
+            let hit = service.hit("test", &id, rule).await.unwrap();
+            assert!(hit.is_allowed());
+            assert_eq!(hit.remaining, remaining);
+        }
+
+        for _ in 0..2 {
+            let hit = service.hit("test", &id, rule).await.unwrap();
+            assert!(!hit.is_allowed());
+            assert!((1..=60).contains(&hit.retry_after.unwrap()));
+        }
+
+        let hit = service
+            .hit("test", &id, RateLimitRule::new(4, 60))
+            .await
+            .unwrap();
+        assert!(hit.is_allowed());
+        assert_eq!(hit.remaining, 0);
+
+        assert!(
+            service
+                .hit("test", &snowflake().to_string(), rule)
+                .await
+                .unwrap()
+                .is_allowed()
+        );
+    }
+}
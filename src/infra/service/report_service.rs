@@ -0,0 +1,215 @@
+use crate::{
+    dto::report::{ReportDto, ReportGroupDto},
+    entity::{self, ReportReason, ReportTarget},
+    repository::{AuditRepository, ReportRepository},
+    snowflake,
+    state::Database,
+};
+
+/// Service for user-submitted reports and the moderation queue.
+pub struct ReportService {
+    db: Database,
+    repo: ReportRepository,
+    audit_repo: AuditRepository,
+}
+
+impl ReportService {
+    /// Creates a new service instance.
+    pub fn new(db: Database) -> Self {
+        Self {
+            repo: ReportRepository::new(db.clone()),
+            audit_repo: AuditRepository::new(db.clone()),
+            db,
+        }
+    }
+
+    /// Reports a post or user.
+    ///
+    /// Returns `false` if the reporter has already reported the target.
+    pub async fn report(
+        &self,
+        reporter_id: i64,
+        target_type: ReportTarget,
+        target_id: i64,
+        reason: ReportReason,
+        details: Option<String>,
+    ) -> Option<bool> {
+        self.repo
+            .create_report(entity::Report {
+                id: snowflake(),
+                reporter_id,
+                target_type,
+                target_id,
+                reason,
+                details,
+            })
+            .await
+    }
+
+    /// Reported targets, most reported first.
+    ///
+    /// `before` is the report count and last report id of the last target
+    /// of the previous page.
+    pub async fn get_report_queue(
+        &self,
+        target_type: Option<ReportTarget>,
+        limit: Option<u64>,
+        before: Option<(u64, i64)>,
+    ) -> Vec<ReportGroupDto> {
+        self.repo
+            .get_report_groups(target_type, limit, before)
+            .await
+            .into_iter()
+            .map(|group| group.into())
+            .collect()
+    }
+
+    /// Latest reports of the target.
+    pub async fn get_reports_of_target(
+        &self,
+        target_type: ReportTarget,
+        target_id: i64,
+    ) -> Vec<ReportDto> {
+        self.repo
+            .get_reports_of_target(target_type, target_id)
+            .await
+            .into_iter()
+            .map(|report| report.into())
+            .collect()
+    }
+
+    /// Dismisses reports of the target and records it in the audit log.
+    ///
+    /// Returns `false` if the target has no reports.
+    pub async fn dismiss_reports(
+        &self,
+        actor_id: i64,
+        target_type: ReportTarget,
+        target_id: i64,
+    ) -> Option<bool> {
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        let count = self
+            .repo
+            .delete_reports_of_target(&mut tx, target_type, target_id)
+            .await?;
+
+        if count == 0 {
+            return Some(false);
+        }
+
+        self.audit_repo
+            .create_audit_entry(
+                &mut tx,
+                entity::AuditEntry {
+                    id: snowflake(),
+                    actor_id,
+                    action: String::from("dismiss_reports"),
+                    target_id,
+                    details: Some(format!("{count} reports")),
+                },
+            )
+            .await?;
+
+        tx.commit().await.ok()?;
+
+        Some(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::test_db;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn reports() {
+        let service = ReportService::new(test_db().await);
+
+        for reporter_id in [1006, 1007, 1008] {
+            assert!(
+                service
+                    .report(
+                        reporter_id,
+                        ReportTarget::Post,
+                        4001,
+                        ReportReason::Spam,
+                        None
+                    )
+                    .await
+                    .unwrap()
+            );
+        }
+        assert!(
+            !service
+                .report(1006, ReportTarget::Post, 4001, ReportReason::Other, None)
+                .await
+                .unwrap()
+        );
+        service
+            .report(
+                1006,
+                ReportTarget::User,
+                1001,
+                ReportReason::Impersonation,
+                Some(String::from("fake account")),
+            )
+            .await
+            .unwrap();
+
+        let queue = service.get_report_queue(None, None, None).await;
+        assert_eq!(
+            (
+                queue[0].target_type,
+                queue[0].target_id,
+                queue[0].report_count
+            ),
+            (ReportTarget::Post, 4001, 3)
+        );
+        assert_eq!(queue[0].reasons, [ReportReason::Spam]);
+
+        let next = service
+            .get_report_queue(
+                None,
+                Some(1),
+                Some((queue[0].report_count, queue[0].last_report_id)),
+            )
+            .await;
+        assert_eq!(
+            (next.len(), next[0].target_type, next[0].target_id),
+            (1, ReportTarget::User, 1001)
+        );
+
+        let queue = service
+            .get_report_queue(Some(ReportTarget::User), None, None)
+            .await;
+        assert_eq!((queue.len(), queue[0].target_id), (1, 1001));
+
+        let reports = service
+            .get_reports_of_target(ReportTarget::User, 1001)
+            .await;
+        assert_eq!(reports[0].details.as_deref(), Some("fake account"));
+
+        assert!(
+            service
+                .dismiss_reports(1001, ReportTarget::Post, 4001)
+                .await
+                .unwrap()
+        );
+        assert!(
+            service
+                .dismiss_reports(1001, ReportTarget::User, 1001)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !service
+                .dismiss_reports(1001, ReportTarget::User, 1001)
+                .await
+                .unwrap()
+        );
+        assert!(service.get_report_queue(None, None, None).await.is_empty());
+    }
+}
@@ -1,14 +1,28 @@
-use crate::{state::Redis, util::timestamp};
+use crate::{
+    snowflake,
+    snowflake::snowflake_timestamp,
+    state::Redis,
+    util::{timestamp, timestamp_millis},
+};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
 use redis::AsyncCommands;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
+
+/// 15 minutes
+static AUTH_TOKEN_TTL: u64 = 60 * 15;
 
 /// 90 days
-static AUTH_TOKEN_TTL: u64 = 3600 * 24 * 90;
+static REFRESH_TOKEN_TTL: u64 = 3600 * 24 * 90;
+
+/// 24 hours
+static EMAIL_VERIFICATION_TOKEN_TTL: u64 = 3600 * 24;
+
+/// Revocation timestamps below this are in seconds rather than milliseconds
+static LEGACY_REVOCATION_THRESHOLD: u64 = 10u64.pow(12);
 
 /// Basic user account authentication token
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AuthToken {
     /// User ID (sub)
     pub id: i64,
@@ -18,6 +32,8 @@ pub struct AuthToken {
     pub iat: u64,
     /// Expires at (as Unix timestamp)
     pub exp: u64,
+    /// Unique token identifier
+    pub jti: String,
 }
 
 impl WebToken for AuthToken {
@@ -32,6 +48,10 @@ impl WebToken for AuthToken {
     fn iat(&self) -> u64 {
         self.iat
     }
+
+    fn jti(&self) -> Option<&str> {
+        Some(&self.jti)
+    }
 }
 
 impl AuthToken {
@@ -44,6 +64,7 @@ impl AuthToken {
             username,
             iat,
             exp: iat + AUTH_TOKEN_TTL,
+            jti: snowflake().to_string(),
         }
     }
 }
@@ -53,6 +74,80 @@ pub fn new_auth_token_service(redis: Redis, secret: &str) -> TokenService<AuthTo
     TokenService::new(redis, secret, String::from("auth-token"), AUTH_TOKEN_TTL)
 }
 
+/// Long-lived token for obtaining new [`AuthToken`]s
+///
+/// Every refresh token is single-use; it is rotated into a new token of the
+/// same family on each refresh.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RefreshToken {
+    /// User ID (sub)
+    pub id: i64,
+    /// Token family, shared by all tokens rotated from the same login
+    pub family: String,
+    /// Issued at (as Unix timestamp)
+    pub iat: u64,
+    /// Expires at (as Unix timestamp)
+    pub exp: u64,
+    /// Unique token identifier
+    pub jti: String,
+}
+
+impl WebToken for RefreshToken {
+    fn sub(&self) -> i64 {
+        self.id
+    }
+
+    fn exp(&self) -> u64 {
+        self.exp
+    }
+
+    fn iat(&self) -> u64 {
+        self.iat
+    }
+
+    fn jti(&self) -> Option<&str> {
+        Some(&self.jti)
+    }
+}
+
+impl RefreshToken {
+    /// Creates a new refresh token, starting a new token family
+    pub fn new(id: i64) -> Self {
+        let iat = timestamp();
+
+        RefreshToken {
+            id,
+            family: snowflake().to_string(),
+            iat,
+            exp: iat + REFRESH_TOKEN_TTL,
+            jti: snowflake().to_string(),
+        }
+    }
+
+    /// Creates the successor of the token in the same family
+    fn successor(&self) -> Self {
+        let iat = timestamp();
+
+        RefreshToken {
+            id: self.id,
+            family: self.family.clone(),
+            iat,
+            exp: iat + REFRESH_TOKEN_TTL,
+            jti: snowflake().to_string(),
+        }
+    }
+}
+
+/// Creates a new [`RefreshToken`] service.
+pub fn new_refresh_token_service(redis: Redis, secret: &str) -> TokenService<RefreshToken> {
+    TokenService::new(
+        redis,
+        secret,
+        String::from("refresh-token"),
+        REFRESH_TOKEN_TTL,
+    )
+}
+
 /// Service for managing JWT tokens backed by Redis.
 pub struct TokenService<T> {
     redis: Redis,
@@ -75,6 +170,23 @@ pub trait WebToken {
 
     /// Returns the issue time as a UTC timestamp.
     fn iat(&self) -> u64;
+
+    /// Returns the unique token identifier, if token can be revoked
+    /// individually.
+    fn jti(&self) -> Option<&str> {
+        None
+    }
+
+    /// Returns the issue time as a UTC timestamp in milliseconds.
+    ///
+    /// Taken from the `jti` if it is a snowflake, as `iat` only has second
+    /// precision.
+    fn iat_millis(&self) -> u64 {
+        self.jti()
+            .and_then(|jti| jti.parse().ok())
+            .map(snowflake_timestamp)
+            .unwrap_or(self.iat() * 1000)
+    }
 }
 
 impl<T> TokenService<T>
@@ -99,17 +211,43 @@ where
     }
 
     /// Revokes all tokens associated with the given subject.
+    ///
+    /// Returns after the millisecond of the revocation is over, so tokens
+    /// signed afterwards are not revoked.
     pub async fn revoke_tokens_for(&self, sub: i64) {
         let key = format!("{}:token-revocation:{}", self.key, sub);
 
         redis::pipe()
             .atomic()
-            .set(&key, timestamp())
+            .set(&key, timestamp_millis())
             .expire(&key, self.ttl as i64 + 1)
             .ignore()
             .query_async::<()>(&mut self.redis.client())
             .await
             .ok();
+
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+
+    /// Revokes a single token until its expiration.
+    ///
+    /// Has no effect on tokens without `jti`.
+    pub async fn revoke_token(&self, token: &T) {
+        let Some(jti) = token.jti() else {
+            return;
+        };
+
+        let ttl = token.exp().saturating_sub(timestamp());
+
+        if ttl == 0 {
+            return;
+        }
+
+        self.redis
+            .client()
+            .set_ex::<_, _, ()>(format!("{}:jti-revocation:{}", self.key, jti), 1, ttl)
+            .await
+            .ok();
     }
 
     /// Validates and decodes the provided JWT string.
@@ -132,17 +270,155 @@ where
             .await
             .ok()?
         {
-            let iat = token.iat();
-
-            if iat <= revocation_timestamp {
+            // Revocations stored with second precision cover the whole second.
+            let revocation_timestamp = if revocation_timestamp < LEGACY_REVOCATION_THRESHOLD {
+                revocation_timestamp * 1000 + 999
+            } else {
+                revocation_timestamp
+            };
+
+            if token.iat_millis() <= revocation_timestamp {
                 return None;
             }
         }
 
+        if let Some(jti) = token.jti()
+            && self
+                .redis
+                .client()
+                .exists::<_, bool>(format!("{}:jti-revocation:{}", self.key, jti))
+                .await
+                .ok()?
+        {
+            return None;
+        }
+
         Some(token)
     }
 }
 
+/// Token mailed to an address to prove its ownership
+#[derive(Serialize, Deserialize)]
+pub struct EmailVerificationToken {
+    /// User ID (sub)
+    pub id: i64,
+    /// ID of the email being verified
+    pub email_id: i64,
+    /// Email address being verified
+    pub email: String,
+    /// Issued at (as Unix timestamp)
+    pub iat: u64,
+    /// Expires at (as Unix timestamp)
+    pub exp: u64,
+    /// Unique token identifier
+    pub jti: String,
+}
+
+impl WebToken for EmailVerificationToken {
+    fn sub(&self) -> i64 {
+        self.id
+    }
+
+    fn exp(&self) -> u64 {
+        self.exp
+    }
+
+    fn iat(&self) -> u64 {
+        self.iat
+    }
+
+    fn jti(&self) -> Option<&str> {
+        Some(&self.jti)
+    }
+}
+
+impl EmailVerificationToken {
+    /// Creates a new email verification token
+    pub fn new(id: i64, email_id: i64, email: String) -> Self {
+        let iat = timestamp();
+
+        EmailVerificationToken {
+            id,
+            email_id,
+            email,
+            iat,
+            exp: iat + EMAIL_VERIFICATION_TOKEN_TTL,
+            jti: snowflake().to_string(),
+        }
+    }
+}
+
+/// Creates a new [`EmailVerificationToken`] service.
+pub fn new_email_verification_token_service(
+    redis: Redis,
+    secret: &str,
+) -> TokenService<EmailVerificationToken> {
+    TokenService::new(
+        redis,
+        secret,
+        String::from("email-verification-token"),
+        EMAIL_VERIFICATION_TOKEN_TTL,
+    )
+}
+
+impl TokenService<RefreshToken> {
+    fn family_key(&self, family: &str) -> String {
+        format!("{}:family:{}", self.key, family)
+    }
+
+    /// Signs the token, marking it as the latest token of its family.
+    pub async fn sign_latest(&self, token: RefreshToken) -> String {
+        self.redis
+            .client()
+            .set_ex::<_, _, ()>(self.family_key(&token.family), &token.jti, self.ttl)
+            .await
+            .ok();
+
+        self.sign(token).await
+    }
+
+    /// Validates the refresh token and rotates it into a new one.
+    ///
+    /// Replaying a token that is not the latest of its family revokes the
+    /// whole family, rejecting any token issued from the same login.
+    pub async fn rotate(&self, jwt: &str) -> Option<(RefreshToken, String)> {
+        let token = self.validate(jwt).await?;
+        let successor = token.successor();
+        let key = self.family_key(&token.family);
+
+        let latest = redis::cmd("SET")
+            .arg(&key)
+            .arg(&successor.jti)
+            .arg("XX")
+            .arg("GET")
+            .arg("EX")
+            .arg(self.ttl)
+            .query_async::<Option<String>>(&mut self.redis.client())
+            .await
+            .ok()?;
+
+        if latest.as_deref() != Some(token.jti.as_str()) {
+            tracing::warn!(sub = token.id, "Refresh token reuse detected");
+            self.revoke_family(&token).await;
+
+            return None;
+        }
+
+        let refresh_token = self.sign(successor.clone()).await;
+
+        Some((successor, refresh_token))
+    }
+
+    /// Revokes every token in the family of the given token.
+    pub async fn revoke_family(&self, token: &RefreshToken) {
+        self.redis
+            .client()
+            .del::<_, ()>(self.family_key(&token.family))
+            .await
+            .ok();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,6 +431,8 @@ mod tests {
         sub: i64,
         iat: u64,
         exp: u64,
+        #[serde(default)]
+        jti: Option<String>,
     }
 
     impl WebToken for SampleToken {
@@ -169,6 +447,10 @@ mod tests {
         fn sub(&self) -> i64 {
             self.sub
         }
+
+        fn jti(&self) -> Option<&str> {
+            self.jti.as_deref()
+        }
     }
 
     static TOKEN_TTL: u64 = 60;
@@ -189,6 +471,7 @@ mod tests {
                 iat: timestamp() - 1,
                 exp: timestamp() + TOKEN_TTL - 1,
                 sub: 0,
+                jti: None,
             })
             .await;
 
@@ -197,6 +480,7 @@ mod tests {
                 iat: timestamp() + 16,
                 exp: timestamp() + 16 + TOKEN_TTL,
                 sub: 0,
+                jti: None,
             })
             .await;
 
@@ -210,6 +494,42 @@ mod tests {
         assert!(token_service.validate(&token_from_future).await.is_some());
     }
 
+    #[serial]
+    #[tokio::test]
+    #[test_log::test]
+    async fn same_second_revocation() {
+        let token_service = TokenService::<SampleToken>::new(
+            test_redis().await,
+            "jwt-secret",
+            String::from("test-5-sample-token"),
+            TOKEN_TTL,
+        );
+
+        let sample_token = || SampleToken {
+            iat: timestamp(),
+            exp: timestamp() + TOKEN_TTL,
+            sub: 4,
+            jti: Some(snowflake().to_string()),
+        };
+
+        let old_token = token_service.sign(sample_token()).await;
+        token_service.revoke_tokens_for(4).await;
+        let new_token = token_service.sign(sample_token()).await;
+
+        assert!(token_service.validate(&old_token).await.is_none());
+        assert!(token_service.validate(&new_token).await.is_some());
+
+        // Legacy revocations cover the whole second they were stored at.
+        token_service
+            .redis
+            .client()
+            .set::<_, _, ()>("test-5-sample-token:token-revocation:4", timestamp())
+            .await
+            .unwrap();
+
+        assert!(token_service.validate(&new_token).await.is_none());
+    }
+
     #[serial]
     #[tokio::test]
     #[test_log::test]
@@ -226,10 +546,67 @@ mod tests {
                 iat: timestamp() - TOKEN_TTL - 1,
                 exp: timestamp() - 1,
                 sub: 1,
+                jti: None,
             })
             .await;
 
         assert!(token_service.validate(&token).await.is_none());
         assert!(token_service.validate("invalid-token").await.is_none());
     }
+
+    #[serial]
+    #[tokio::test]
+    #[test_log::test]
+    async fn single_revocation() {
+        let token_service = TokenService::<SampleToken>::new(
+            test_redis().await,
+            "jwt-secret",
+            String::from("test-3-sample-token"),
+            TOKEN_TTL,
+        );
+
+        let sample_token = |jti: i64| SampleToken {
+            iat: timestamp() - 1,
+            exp: timestamp() + TOKEN_TTL - 1,
+            sub: 2,
+            jti: Some(format!("{jti}")),
+        };
+
+        let jti = snowflake();
+        let token = token_service.sign(sample_token(jti)).await;
+        let other_token = token_service.sign(sample_token(snowflake())).await;
+
+        assert!(token_service.validate(&token).await.is_some());
+
+        token_service.revoke_token(&sample_token(jti)).await;
+
+        assert!(token_service.validate(&token).await.is_none());
+        assert!(token_service.validate(&other_token).await.is_some());
+    }
+
+    #[serial]
+    #[tokio::test]
+    #[test_log::test]
+    async fn refresh_rotation() {
+        let token_service = TokenService::<RefreshToken>::new(
+            test_redis().await,
+            "jwt-secret",
+            String::from("test-4-refresh-token"),
+            TOKEN_TTL,
+        );
+
+        let first_token = token_service.sign_latest(RefreshToken::new(3)).await;
+
+        let (rotated, second_token) = token_service.rotate(&first_token).await.unwrap();
+        assert_eq!(rotated.id, 3);
+
+        let (_, third_token) = token_service.rotate(&second_token).await.unwrap();
+
+        // Replaying an already rotated token revokes the whole family.
+        assert!(token_service.rotate(&first_token).await.is_none());
+        assert!(token_service.rotate(&third_token).await.is_none());
+
+        let other_family = token_service.sign_latest(RefreshToken::new(3)).await;
+        assert!(token_service.rotate(&other_family).await.is_some());
+    }
 }
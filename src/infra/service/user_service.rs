@@ -1,13 +1,18 @@
 use crate::{
-    dto::user::{FullProfileDto, UserDto, UserStatsDto},
+    dto::user::{FullProfileDto, RelationshipDto, UserDto, UserStatsDto},
     entity,
-    repository::{ThreadRepository, UserRepository},
+    repository::{
+        AttachmentRepository, AuditRepository, PostRepository, ThreadRepository, UserRepository,
+    },
     snowflake,
-    state::Database,
+    state::{Database, DeletedPostsPolicy},
     util::{argon2_hash, argon2_verify},
 };
 use sqlx::types::BitVec;
 
+/// Number of accounts fetched per purge batch
+static PURGE_BATCH_SIZE: i64 = 128;
+
 /// Service struct for handling user-related operations such as creating,
 /// updating, deleting, and fetching users.
 ///
@@ -15,6 +20,9 @@ use sqlx::types::BitVec;
 pub struct UserService {
     db: Database,
     thread_repo: ThreadRepository,
+    attachment_repo: AttachmentRepository,
+    post_repo: PostRepository,
+    audit_repo: AuditRepository,
     repo: UserRepository,
 }
 
@@ -24,6 +32,9 @@ impl UserService {
         Self {
             repo: UserRepository::new(db.clone()),
             thread_repo: ThreadRepository::new(db.clone()),
+            attachment_repo: AttachmentRepository::new(db.clone()),
+            post_repo: PostRepository::new(db.clone()),
+            audit_repo: AuditRepository::new(db.clone()),
             db,
         }
     }
@@ -53,24 +64,306 @@ impl UserService {
         self.repo.get_user_stats_by_id(id).await
     }
 
-    /// Fetches user's stats from its ID.
+    /// Fetches users that the user follows, as seen by the viewer.
     pub async fn get_follows(
         &self,
         id: i64,
+        viewer_id: Option<i64>,
         limit: Option<u64>,
         before: Option<i64>,
     ) -> Vec<UserDto> {
-        self.repo.get_follows(id, limit, before).await
+        self.repo.get_follows(id, viewer_id, limit, before).await
     }
 
-    /// Fetches user's stats from its ID.
+    /// Fetches followers of the user, as seen by the viewer.
     pub async fn get_followers(
         &self,
         id: i64,
+        viewer_id: Option<i64>,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<UserDto> {
+        self.repo.get_followers(id, viewer_id, limit, before).await
+    }
+
+    /// Whether or not the attachment exists and was uploaded by the user.
+    pub async fn owns_attachment(&self, user_id: i64, attachment_id: i64) -> bool {
+        self.attachment_repo
+            .get_attachment_by_id(attachment_id)
+            .await
+            .is_some_and(|attachment| attachment.user_id == user_id)
+    }
+
+    /// Updates user's profile, returning the updated profile.
+    ///
+    /// Fields set to [`None`] are left unchanged. Returns [`None`] if the
+    /// avatar or banner does not exist.
+    pub async fn update_profile(
+        &self,
+        user_id: i64,
+        bio: Option<&str>,
+        avatar_id: Option<Option<i64>>,
+        banner_id: Option<Option<i64>>,
+    ) -> Option<FullProfileDto> {
+        let attachments: Vec<_> = [avatar_id, banner_id]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .collect();
+
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        if !self
+            .attachment_repo
+            .lock_attachments(&mut tx, &attachments)
+            .await
+        {
+            return None;
+        }
+
+        self.repo
+            .update_profile(&mut tx, user_id, bio, avatar_id, banner_id)
+            .await?;
+
+        tx.commit().await.ok()?;
+
+        self.repo.get_profile_by_id(user_id).await
+    }
+
+    /// Changes username of the user, keeping the old one in the username
+    /// history. Returns the updated profile.
+    ///
+    /// Returns [`None`] if the username is taken.
+    pub async fn change_username(&self, user_id: i64, username: &str) -> Option<FullProfileDto> {
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        self.repo.record_username(&mut tx, user_id).await?;
+        self.repo
+            .update_username(&mut tx, user_id, username)
+            .await?;
+
+        tx.commit().await.ok()?;
+
+        self.repo.get_profile_by_id(user_id).await
+    }
+
+    /// Whether or not the username has been released by someone other than
+    /// `user_id` in the last `cooldown` seconds.
+    pub async fn is_username_reserved(
+        &self,
+        username: &str,
+        user_id: Option<i64>,
+        cooldown: u64,
+    ) -> bool {
+        self.repo
+            .is_username_reserved(username, user_id, cooldown)
+            .await
+            .unwrap_or(true)
+    }
+
+    /// Finds the user that released the username in the last `cooldown`
+    /// seconds.
+    pub async fn get_user_by_previous_username(
+        &self,
+        username: &str,
+        cooldown: u64,
+    ) -> Option<UserDto> {
+        self.repo
+            .get_user_by_previous_username(username, cooldown)
+            .await
+    }
+
+    /// Fetches user with its flags.
+    pub async fn get_user_entity_by_id(&self, id: i64) -> Option<entity::User> {
+        self.repo.get_user_entity_by_id(id).await
+    }
+
+    /// Sets or clears the user flag on behalf of an admin, recording the
+    /// change in the audit log.
+    ///
+    /// Returns `false` if the flag already has the value.
+    pub async fn set_user_flag(
+        &self,
+        actor_id: i64,
+        user_id: i64,
+        flag: entity::UserFlag,
+        value: bool,
+    ) -> Option<bool> {
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        if !self
+            .repo
+            .set_user_flag(&mut tx, user_id, flag.bit(), value)
+            .await?
+        {
+            return Some(false);
+        }
+
+        self.audit_repo
+            .create_audit_entry(
+                &mut tx,
+                entity::AuditEntry {
+                    id: snowflake(),
+                    actor_id,
+                    action: String::from(if value { "grant_flag" } else { "revoke_flag" }),
+                    target_id: user_id,
+                    details: Some(String::from(flag.name())),
+                },
+            )
+            .await?;
+
+        tx.commit().await.ok()?;
+
+        Some(true)
+    }
+
+    /// Soft-deletes the account, removing its emails, likes and follows.
+    /// Posts are anonymised or deleted according to the policy.
+    pub async fn delete_account(&self, user_id: i64, policy: DeletedPostsPolicy) -> Option<()> {
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        self.repo.soft_delete_user(&mut tx, user_id).await?;
+
+        match policy {
+            DeletedPostsPolicy::Anonymise => {
+                self.post_repo
+                    .anonymise_posts_of_user(&mut tx, user_id)
+                    .await?
+            }
+            DeletedPostsPolicy::Delete => {
+                self.post_repo
+                    .delete_posts_of_user(&mut tx, user_id)
+                    .await?;
+                self.thread_repo
+                    .delete_empty_threads_of_user(&mut tx, user_id)
+                    .await?
+            }
+        }
+
+        tx.commit().await.ok()
+    }
+
+    /// Hard-deletes accounts deleted more than `grace_period` seconds ago,
+    /// returning the number of purged accounts.
+    ///
+    /// Accounts are purged in batches until none is left. Accounts that
+    /// cannot be purged are logged and skipped until the next run.
+    pub async fn purge_deleted_accounts(&self, grace_period: u64) -> usize {
+        let mut purged = 0;
+        let mut skipped = Vec::new();
+
+        loop {
+            let ids = self
+                .repo
+                .get_purgeable_user_ids(grace_period, &skipped, PURGE_BATCH_SIZE)
+                .await;
+
+            if ids.is_empty() {
+                break;
+            }
+
+            for id in ids {
+                let Ok(mut tx) = self.db.pool().begin().await else {
+                    return purged;
+                };
+
+                if self.repo.purge_user(&mut tx, id).await.is_some() && tx.commit().await.is_ok() {
+                    purged += 1;
+                } else {
+                    tracing::warn!(id, "Could not purge deleted account");
+                    skipped.push(id);
+                }
+            }
+        }
+
+        purged
+    }
+
+    /// Follows the user on behalf of the follower.
+    ///
+    /// Returns `false` if the follower is already following the user.
+    pub async fn follow_user(&self, follower_id: i64, user_id: i64) -> Option<bool> {
+        self.repo.follow_user(follower_id, user_id).await
+    }
+
+    /// Unfollows the user on behalf of the follower.
+    ///
+    /// Returns `false` if the follower is not following the user.
+    pub async fn unfollow_user(&self, follower_id: i64, user_id: i64) -> Option<bool> {
+        self.repo.unfollow_user(follower_id, user_id).await
+    }
+
+    /// Blocks the user on behalf of the blocker, removing follow relations
+    /// between them.
+    ///
+    /// Returns `false` if the blocker is already blocking the user.
+    pub async fn block_user(&self, blocker_id: i64, user_id: i64) -> Option<bool> {
+        let mut tx = self.db.pool().begin().await.ok()?;
+
+        let blocked = self.repo.block_user(&mut tx, blocker_id, user_id).await?;
+
+        tx.commit().await.ok()?;
+
+        Some(blocked)
+    }
+
+    /// Unblocks the user on behalf of the blocker.
+    ///
+    /// Returns `false` if the blocker is not blocking the user.
+    pub async fn unblock_user(&self, blocker_id: i64, user_id: i64) -> Option<bool> {
+        self.repo.unblock_user(blocker_id, user_id).await
+    }
+
+    /// Mutes the user on behalf of the muter.
+    ///
+    /// Returns `false` if the muter is already muting the user.
+    pub async fn mute_user(&self, muter_id: i64, user_id: i64) -> Option<bool> {
+        self.repo.mute_user(muter_id, user_id).await
+    }
+
+    /// Unmutes the user on behalf of the muter.
+    ///
+    /// Returns `false` if the muter is not muting the user.
+    pub async fn unmute_user(&self, muter_id: i64, user_id: i64) -> Option<bool> {
+        self.repo.unmute_user(muter_id, user_id).await
+    }
+
+    /// Whether or not the blocker blocks the user.
+    pub async fn is_blocking(&self, blocker_id: i64, user_id: i64) -> bool {
+        self.repo.is_blocking(blocker_id, user_id).await
+    }
+
+    /// Whether or not the thread is the wall of an user that blocks the user.
+    pub async fn is_blocked_from_thread(&self, thread_id: i64, user_id: i64) -> bool {
+        self.repo.is_blocked_from_thread(thread_id, user_id).await
+    }
+
+    /// Fetches users blocked by the blocker.
+    pub async fn get_blocks(
+        &self,
+        blocker_id: i64,
+        limit: Option<u64>,
+        before: Option<i64>,
+    ) -> Vec<UserDto> {
+        self.repo.get_blocks(blocker_id, limit, before).await
+    }
+
+    /// Fetches users muted by the muter.
+    pub async fn get_mutes(
+        &self,
+        muter_id: i64,
         limit: Option<u64>,
         before: Option<i64>,
     ) -> Vec<UserDto> {
-        self.repo.get_followers(id, limit, before).await
+        self.repo.get_mutes(muter_id, limit, before).await
+    }
+
+    /// Fetches relationship between the caller and an user.
+    pub async fn get_relationship(&self, caller_id: i64, user_id: i64) -> RelationshipDto {
+        self.repo
+            .get_relationship(caller_id, user_id)
+            .await
+            .unwrap_or_default()
     }
 
     /// Checks user's password.
@@ -91,6 +384,13 @@ impl UserService {
         }
     }
 
+    /// Hashes and stores user's new password.
+    pub async fn change_password(&self, user_id: i64, password: String) -> Option<()> {
+        let password_hash = argon2_hash(password).await?;
+
+        self.repo.update_password_hash(user_id, password_hash).await
+    }
+
     /// Creates an account with comment thread and profile.
     ///
     /// Returns full profile of the created user if succeedded.
@@ -108,7 +408,7 @@ impl UserService {
                 entity::User {
                     id: user_id,
                     username: username.clone(),
-                    flags: BitVec::from_elem(2, false),
+                    flags: BitVec::from_elem(entity::USER_FLAGS_LEN, false),
                 },
                 password_hash,
             )
@@ -145,8 +445,9 @@ impl UserService {
             avatar_id: None,
             banner_id: None,
             bio: String::from(""),
-            flags: BitVec::from_elem(2, false),
+            flags: BitVec::from_elem(entity::USER_FLAGS_LEN, false),
             comments_thread_id: thread_id,
+            relationship: None,
         })
     }
 }
@@ -154,7 +455,7 @@ impl UserService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::testutil::test_db;
+    use crate::{service::PostService, testutil::test_db};
     use serial_test::serial;
 
     #[serial]
@@ -185,4 +486,195 @@ mod tests {
                 .await
         );
     }
+
+    #[serial]
+    #[tokio::test]
+    async fn password_change() {
+        let service = UserService::new(test_db().await);
+
+        let username = format!("{}", snowflake());
+        let password = format!("{}", snowflake());
+        let new_password = format!("{}", snowflake());
+        let user = service
+            .create_user(username, password.clone())
+            .await
+            .unwrap();
+
+        service
+            .change_password(user.id, new_password.clone())
+            .await
+            .unwrap();
+
+        assert!(
+            !service
+                .validate_password_of_user_id(user.id, password)
+                .await
+        );
+        assert!(
+            service
+                .validate_password_of_user_id(user.id, new_password)
+                .await
+        );
+        assert!(
+            service
+                .change_password(-1, String::from("password"))
+                .await
+                .is_none()
+        );
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn account_deletion() {
+        let db = test_db().await;
+        let service = UserService::new(db.clone());
+        let post_service = PostService::new(db);
+
+        for policy in [DeletedPostsPolicy::Anonymise, DeletedPostsPolicy::Delete] {
+            let username = format!("{}", snowflake());
+            let password = format!("{}", snowflake());
+            let user = service
+                .create_user(username.clone(), password.clone())
+                .await
+                .unwrap();
+
+            let post = post_service
+                .create_post(user.id, None, String::from("post"), vec![])
+                .await
+                .unwrap();
+            service.follow_user(user.id, 1001).await.unwrap();
+            post_service.like_post(user.id, 4001).await.unwrap();
+
+            service.delete_account(user.id, policy).await.unwrap();
+            assert!(service.delete_account(user.id, policy).await.is_none());
+
+            assert!(service.get_user_by_id(user.id).await.is_none());
+            assert!(service.get_profile_by_username(&username).await.is_none());
+            assert!(
+                !service
+                    .validate_password_of_user_id(user.id, password)
+                    .await
+            );
+            assert!(!service.get_relationship(user.id, 1001).await.is_following);
+
+            match policy {
+                DeletedPostsPolicy::Anonymise => {
+                    assert_eq!(
+                        post_service.get_post_by_id(post.id).await.unwrap().user_id,
+                        0
+                    );
+                    post_service.delete_post(post.id).await.unwrap();
+                }
+                DeletedPostsPolicy::Delete => {
+                    assert!(post_service.get_post_by_id(post.id).await.is_none())
+                }
+            }
+
+            assert!(
+                service
+                    .create_user(username.clone(), String::new())
+                    .await
+                    .is_none()
+            );
+            assert!(service.purge_deleted_accounts(0).await > 0);
+            assert!(service.create_user(username, String::new()).await.is_some());
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn flags() {
+        let db = test_db().await;
+        let service = UserService::new(db.clone());
+        let audit_repo = AuditRepository::new(db);
+
+        for value in [true, false] {
+            assert!(
+                service
+                    .set_user_flag(1001, 1003, entity::UserFlag::Verified, value)
+                    .await
+                    .unwrap()
+            );
+            assert!(
+                !service
+                    .set_user_flag(1001, 1003, entity::UserFlag::Verified, value)
+                    .await
+                    .unwrap()
+            );
+
+            let user = service.get_user_entity_by_id(1003).await.unwrap();
+            assert_eq!(user.is_verified(), value);
+            assert!(!user.is_admin() && !user.is_banned());
+
+            let entry = audit_repo.get_audit_entries_of_target(1003).await.remove(0);
+            assert_eq!(entry.actor_id, 1001);
+            assert_eq!(entry.details.as_deref(), Some("verified"));
+            assert_eq!(
+                entry.action,
+                if value { "grant_flag" } else { "revoke_flag" }
+            );
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn blocks_and_mutes() {
+        let db = test_db().await;
+        let service = UserService::new(db.clone());
+        let post_service = PostService::new(db);
+
+        // user22 follows user02
+        assert!(service.block_user(1002, 1022).await.unwrap());
+        assert!(!service.block_user(1002, 1022).await.unwrap());
+        assert!(service.is_blocking(1002, 1022).await);
+        assert!(service.is_blocked_from_thread(2002, 1022).await);
+        assert!(!service.is_blocked_from_thread(2022, 1002).await);
+
+        let relationship = service.get_relationship(1022, 1002).await;
+        assert!(!relationship.is_following && !relationship.is_blocking);
+        assert!(service.get_relationship(1002, 1022).await.is_blocking);
+
+        let ids: Vec<_> = service
+            .get_blocks(1002, None, None)
+            .await
+            .iter()
+            .map(|user| user.id)
+            .collect();
+        assert_eq!(ids, [1022]);
+
+        // user21, user22 and user23 follow user03
+        let ids: Vec<_> = service
+            .get_followers(1003, Some(1002), None, None)
+            .await
+            .iter()
+            .map(|user| user.id)
+            .collect();
+        assert_eq!(ids, [1023, 1021]);
+
+        assert!(service.unblock_user(1002, 1022).await.unwrap());
+        assert!(!service.unblock_user(1002, 1022).await.unwrap());
+        service.follow_user(1022, 1002).await.unwrap();
+
+        assert!(service.mute_user(1021, 1001).await.unwrap());
+        assert!(!service.mute_user(1021, 1001).await.unwrap());
+        assert!(service.get_relationship(1021, 1001).await.is_muting);
+
+        let posts = post_service.get_timeline(1021, None, None).await;
+        assert!(posts.iter().all(|post| post.user_id != 1001));
+
+        let posts = post_service
+            .get_latest_posts_of_thread(None, Some(1021), None, None)
+            .await;
+        assert!(posts.iter().all(|post| post.user_id != 1001));
+        assert!(
+            post_service
+                .get_latest_posts_of_thread(None, None, None, None)
+                .await
+                .iter()
+                .any(|post| post.user_id == 1001)
+        );
+
+        assert!(service.unmute_user(1021, 1001).await.unwrap());
+        assert!(service.get_mutes(1021, None, None).await.is_empty());
+    }
 }
@@ -0,0 +1,86 @@
+use crate::util::BoxFuture;
+use std::{io::ErrorKind, path::PathBuf};
+
+/// Blob storage for uploaded files.
+///
+/// Keys are generated by the API, such as attachment ids, and are never
+/// taken from user input.
+pub trait Storage: Send + Sync {
+    /// Stores the data under the key, replacing existing data.
+    fn put<'a>(&'a self, key: &'a str, data: &'a [u8]) -> BoxFuture<'a, Option<()>>;
+
+    /// Reads the data of the key, returning `Some(None)` if it does not
+    /// exist.
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Option<Vec<u8>>>>;
+
+    /// Deletes the data of the key, deleting missing keys has no effect.
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<()>>;
+}
+
+/// Storage that keeps files in a local directory, one file per key.
+pub struct LocalStorage {
+    dir: PathBuf,
+}
+
+impl LocalStorage {
+    /// Creates a storage writing into `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put<'a>(&'a self, key: &'a str, data: &'a [u8]) -> BoxFuture<'a, Option<()>> {
+        Box::pin(async move {
+            let path = self.dir.join(key);
+
+            // Written into a temporary file first, so that readers never see
+            // partially written files.
+            let temp_path = self.dir.join(format!(".{key}.tmp"));
+            let result = async {
+                tokio::fs::create_dir_all(&self.dir).await?;
+                tokio::fs::write(&temp_path, data).await?;
+                tokio::fs::rename(&temp_path, &path).await
+            }
+            .await;
+
+            match result {
+                Ok(()) => Some(()),
+                Err(err) => {
+                    tracing::error!(?err, ?path, "Could not write file");
+                    None
+                }
+            }
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Option<Vec<u8>>>> {
+        Box::pin(async move {
+            let path = self.dir.join(key);
+
+            match tokio::fs::read(&path).await {
+                Ok(data) => Some(Some(data)),
+                Err(err) if err.kind() == ErrorKind::NotFound => Some(None),
+                Err(err) => {
+                    tracing::error!(?err, ?path, "Could not read file");
+                    None
+                }
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<()>> {
+        Box::pin(async move {
+            let path = self.dir.join(key);
+
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => Some(()),
+                Err(err) if err.kind() == ErrorKind::NotFound => Some(()),
+                Err(err) => {
+                    tracing::error!(?err, ?path, "Could not delete file");
+                    None
+                }
+            }
+        })
+    }
+}
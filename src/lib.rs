@@ -43,8 +43,8 @@ pub mod testutil;
 pub use app::create_router;
 pub use state::{AppState, Config};
 
-pub use api::{handlers, routes};
-pub use infra::{repository, service};
+pub use api::{extract, handlers, middleware, routes};
+pub use infra::{mailer, media, repository, service, storage};
 
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 
@@ -28,7 +28,9 @@ async fn main() {
 
     axum::serve(
         listener,
-        create_router(state::bootstrap(config).await).await,
+        create_router(state::bootstrap(config).await)
+            .await
+            .into_make_service_with_connect_info::<SocketAddr>(),
     )
     .await
     .unwrap();
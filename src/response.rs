@@ -1,7 +1,10 @@
-use crate::dto::{auth::AuthError, posts::PostError, user::UserError};
+use crate::dto::{
+    attachments::AttachmentError, auth::AuthError, emails::EmailError, moderation::ModerationError,
+    posts::PostError, report::ReportError, user::UserError,
+};
 use axum::{
     Json,
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header::RETRY_AFTER},
     response::{IntoResponse, Response},
 };
 use serde::Serialize;
@@ -20,6 +23,10 @@ pub enum AppError {
     /// Unknown erro
     #[error("Internal server error.")]
     InternalServerError,
+    /// Request budget of the route group is exhausted, retry after given
+    /// seconds.
+    #[error("Too many requests, try again in {0} seconds.")]
+    TooManyRequests(u64),
     /// /auth error types.
     #[error("Authentication error: {0}")]
     AuthError(#[from] AuthError),
@@ -29,6 +36,18 @@ pub enum AppError {
     /// /users error types.
     #[error("User error: {0}")]
     UserError(#[from] UserError),
+    /// /emails error types.
+    #[error("Email error: {0}")]
+    EmailError(#[from] EmailError),
+    /// /mod error types.
+    #[error("Moderation error: {0}")]
+    ModerationError(#[from] ModerationError),
+    /// Report error types.
+    #[error("Report error: {0}")]
+    ReportError(#[from] ReportError),
+    /// /attachments error types.
+    #[error("Attachment error: {0}")]
+    AttachmentError(#[from] AttachmentError),
 }
 
 /// Error sent back to clients
@@ -65,9 +84,27 @@ impl AppError {
     pub fn status_code(&self) -> StatusCode {
         match self {
             AppError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
             AppError::AuthError(err) => err.into(),
             AppError::PostError(err) => err.into(),
             AppError::UserError(err) => err.into(),
+            AppError::EmailError(err) => err.into(),
+            AppError::ModerationError(err) => err.into(),
+            AppError::ReportError(err) => err.into(),
+            AppError::AttachmentError(err) => err.into(),
+        }
+    }
+
+    /// Seconds the client should wait before retrying, sent as `Retry-After`
+    /// header
+    ///
+    /// Only set for rate limited requests, other errors carrying a duration
+    /// (such as suspensions) are not retryable and keep it in the body.
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            AppError::TooManyRequests(seconds)
+            | AppError::AuthError(AuthError::TooManyAttempts(seconds)) => Some(*seconds),
+            _ => None,
         }
     }
 
@@ -83,7 +120,16 @@ impl AppError {
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (self.status_code(), self.into_dto()).into_response()
+        let retry_after = self.retry_after();
+        let mut response = (self.status_code(), self.into_dto()).into_response();
+
+        if let Some(seconds) = retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+
+        response
     }
 }
 
@@ -41,6 +41,11 @@ pub fn snowflake() -> i64 {
     (timestamp << 22) | *increment
 }
 
+/// Unix timestamp in milliseconds that the snowflake is generated at.
+pub fn snowflake_timestamp(snowflake: i64) -> u64 {
+    (snowflake >> 22) as u64 + *EPOCH
+}
+
 #[cfg(test)]
 #[test]
 #[serial_test::serial]
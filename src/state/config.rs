@@ -11,6 +11,173 @@ pub struct Config {
     pub jwt_secret: String,
     /// Whether or not to allow account registrations.
     pub allow_account_creation: bool,
+    /// Minimum character count of passwords
+    pub password_min_length: usize,
+    /// Maximum character count of passwords
+    pub password_max_length: usize,
+    /// Directory to write outgoing mails into, mails are only logged if not
+    /// set
+    pub mail_dir: Option<String>,
+    /// Count of reverse proxies in front of the API, whose `X-Forwarded-For`
+    /// entries are trusted for reading client IP
+    pub trusted_proxies: usize,
+    /// Seconds a released username is reserved for its previous owner
+    pub username_cooldown: u64,
+    /// Seconds a deleted account is kept before it is purged
+    pub account_deletion_grace_period: u64,
+    /// What happens to posts of deleted accounts
+    pub deleted_posts_policy: DeletedPostsPolicy,
+    /// Login attempts allowed per client IP
+    pub login_rate_limit_ip: RateLimitRule,
+    /// Login attempts allowed per username
+    pub login_rate_limit_username: RateLimitRule,
+    /// Registration attempts allowed per client IP
+    pub register_rate_limit_ip: RateLimitRule,
+    /// Registration attempts allowed per username
+    pub register_rate_limit_username: RateLimitRule,
+    /// Verification and password reset mails allowed per client IP
+    pub mail_rate_limit_ip: RateLimitRule,
+    /// Verification and password reset mails allowed per email
+    pub mail_rate_limit_email: RateLimitRule,
+    /// Request budgets of `/users` routes
+    pub users_rate_limit: RouteRateLimit,
+    /// Request budgets of `/posts` routes
+    pub posts_rate_limit: RouteRateLimit,
+    /// Request budgets of `/threads` routes
+    pub threads_rate_limit: RouteRateLimit,
+    /// Request budgets of `/timeline` routes
+    pub timeline_rate_limit: RouteRateLimit,
+    /// Request budgets of `/attachments` routes
+    pub attachments_rate_limit: RouteRateLimit,
+    /// Directory that uploaded files are stored in
+    pub storage_dir: String,
+    /// Maximum size of uploaded files, in bytes
+    pub attachment_max_size: u64,
+    /// MIME types allowed for uploaded files
+    pub attachment_types: MimeTypes,
+    /// Maximum pixel count of uploaded images, larger images are rejected
+    /// before decoding
+    pub image_max_pixels: u64,
+    /// Seconds an uploaded file is kept before it is deleted if no post or
+    /// profile references it
+    pub orphaned_attachment_grace_period: u64,
+}
+
+/// Retention policy for posts of deleted accounts, parsed from `anonymise`
+/// or `delete`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeletedPostsPolicy {
+    /// Posts are kept, attributed to the placeholder `deleted` user
+    Anonymise,
+    /// Posts are deleted along with their likes
+    Delete,
+}
+
+impl std::str::FromStr for DeletedPostsPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "anonymise" => Ok(Self::Anonymise),
+            "delete" => Ok(Self::Delete),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Sliding window rate limit, parsed from `<limit>/<window seconds>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimitRule {
+    /// Maximum request count in a window
+    pub limit: u64,
+    /// Window length, in seconds
+    pub window: u64,
+}
+
+impl RateLimitRule {
+    /// Creates a rule allowing `limit` requests per `window` seconds.
+    pub const fn new(limit: u64, window: u64) -> Self {
+        Self { limit, window }
+    }
+}
+
+impl std::str::FromStr for RateLimitRule {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (limit, window) = s.split_once('/').ok_or(())?;
+        let limit = limit.trim().parse().map_err(|_| ())?;
+        let window = window.trim().parse().map_err(|_| ())?;
+
+        if window == 0 {
+            return Err(());
+        }
+
+        Ok(Self { limit, window })
+    }
+}
+
+/// Request budgets of a route group, parsed from
+/// `<anonymous rule>,<authenticated rule>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RouteRateLimit {
+    /// Budget of anonymous callers, counted per client IP
+    pub anonymous: RateLimitRule,
+    /// Budget of authenticated callers, counted per user
+    pub authenticated: RateLimitRule,
+}
+
+impl RouteRateLimit {
+    /// Creates budgets for anonymous and authenticated callers.
+    pub const fn new(anonymous: RateLimitRule, authenticated: RateLimitRule) -> Self {
+        Self {
+            anonymous,
+            authenticated,
+        }
+    }
+}
+
+impl std::str::FromStr for RouteRateLimit {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (anonymous, authenticated) = s.split_once(',').ok_or(())?;
+
+        Ok(Self {
+            anonymous: anonymous.parse()?,
+            authenticated: authenticated.parse()?,
+        })
+    }
+}
+
+/// List of MIME types, parsed from a comma-separated list such as
+/// `image/png,image/jpeg`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MimeTypes(pub Vec<String>);
+
+impl MimeTypes {
+    /// Whether or not the list contains the MIME type.
+    pub fn contains(&self, mime_type: &str) -> bool {
+        self.0.iter().any(|allowed| allowed == mime_type)
+    }
+}
+
+impl std::str::FromStr for MimeTypes {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mime_types: Vec<_> = s
+            .split(',')
+            .map(|mime_type| mime_type.trim().to_lowercase())
+            .filter(|mime_type| !mime_type.is_empty())
+            .collect();
+
+        if mime_types.is_empty() {
+            return Err(());
+        }
+
+        Ok(Self(mime_types))
+    }
 }
 
 impl Config {
@@ -27,6 +194,63 @@ impl Config {
             redis_url: env::var("REDIS_URL")?,
             jwt_secret: env::var("JWT_SECRET")?,
             allow_account_creation: env::var("DISABLE_ACCOUNT_CREATION").is_err(),
+            password_min_length: parse_var("PASSWORD_MIN_LENGTH", 8),
+            password_max_length: parse_var("PASSWORD_MAX_LENGTH", 128),
+            mail_dir: env::var("MAIL_DIR").ok(),
+            trusted_proxies: env::var("TRUST_PROXY").map_or(0, |value| value.parse().unwrap_or(1)),
+            username_cooldown: parse_var("USERNAME_COOLDOWN", 30 * 24 * 3600),
+            account_deletion_grace_period: parse_var(
+                "ACCOUNT_DELETION_GRACE_PERIOD",
+                30 * 24 * 3600,
+            ),
+            deleted_posts_policy: parse_var("DELETED_POSTS_POLICY", DeletedPostsPolicy::Anonymise),
+            login_rate_limit_ip: parse_var("LOGIN_RATE_LIMIT_IP", RateLimitRule::new(20, 600)),
+            login_rate_limit_username: parse_var(
+                "LOGIN_RATE_LIMIT_USERNAME",
+                RateLimitRule::new(10, 600),
+            ),
+            register_rate_limit_ip: parse_var(
+                "REGISTER_RATE_LIMIT_IP",
+                RateLimitRule::new(5, 3600),
+            ),
+            register_rate_limit_username: parse_var(
+                "REGISTER_RATE_LIMIT_USERNAME",
+                RateLimitRule::new(3, 3600),
+            ),
+            mail_rate_limit_ip: parse_var("MAIL_RATE_LIMIT_IP", RateLimitRule::new(10, 3600)),
+            mail_rate_limit_email: parse_var("MAIL_RATE_LIMIT_EMAIL", RateLimitRule::new(3, 3600)),
+            users_rate_limit: parse_var(
+                "USERS_RATE_LIMIT",
+                RouteRateLimit::new(RateLimitRule::new(60, 60), RateLimitRule::new(120, 60)),
+            ),
+            posts_rate_limit: parse_var(
+                "POSTS_RATE_LIMIT",
+                RouteRateLimit::new(RateLimitRule::new(120, 60), RateLimitRule::new(300, 60)),
+            ),
+            threads_rate_limit: parse_var(
+                "THREADS_RATE_LIMIT",
+                RouteRateLimit::new(RateLimitRule::new(120, 60), RateLimitRule::new(300, 60)),
+            ),
+            timeline_rate_limit: parse_var(
+                "TIMELINE_RATE_LIMIT",
+                RouteRateLimit::new(RateLimitRule::new(120, 60), RateLimitRule::new(300, 60)),
+            ),
+            attachments_rate_limit: parse_var(
+                "ATTACHMENTS_RATE_LIMIT",
+                RouteRateLimit::new(RateLimitRule::new(120, 60), RateLimitRule::new(300, 60)),
+            ),
+            storage_dir: env::var("STORAGE_DIR").unwrap_or(String::from("storage")),
+            attachment_max_size: parse_var("ATTACHMENT_MAX_SIZE", 8 * 1024 * 1024),
+            attachment_types: parse_var(
+                "ATTACHMENT_TYPES",
+                MimeTypes(
+                    ["image/png", "image/jpeg", "image/gif", "image/webp"]
+                        .map(String::from)
+                        .to_vec(),
+                ),
+            ),
+            image_max_pixels: parse_var("IMAGE_MAX_PIXELS", 40_000_000),
+            orphaned_attachment_grace_period: parse_var("ORPHANED_ATTACHMENT_GRACE_PERIOD", 86400),
         };
 
         tracing::info!(?config, "Config loaded");
@@ -34,3 +258,39 @@ impl Config {
         Ok(config)
     }
 }
+
+/// Parses an optional environment variable, falling back to default value if
+/// it is not set or malformed.
+fn parse_var<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limit_rules() {
+        assert_eq!("20/600".parse(), Ok(RateLimitRule::new(20, 600)));
+        assert_eq!(" 5 / 60 ".parse(), Ok(RateLimitRule::new(5, 60)));
+
+        for rule in ["20", "20/0", "/600", "x/600", "-1/600"] {
+            assert_eq!(rule.parse::<RateLimitRule>(), Err(()));
+        }
+
+        assert_eq!(
+            "60/60,120/60".parse(),
+            Ok(RouteRateLimit::new(
+                RateLimitRule::new(60, 60),
+                RateLimitRule::new(120, 60)
+            ))
+        );
+
+        for budget in ["60/60", "60/60,", "60/60,120/0", "60/60;120/60"] {
+            assert_eq!(budget.parse::<RouteRateLimit>(), Err(()));
+        }
+    }
+}
@@ -4,13 +4,23 @@ mod redis;
 
 mod config;
 
-pub use config::Config;
+mod tasks;
+
+pub use config::{Config, DeletedPostsPolicy, MimeTypes, RateLimitRule, RouteRateLimit};
 pub use database::Database;
 pub use redis::Redis;
 
-use crate::service::{
-    PostService, UserService,
-    token_service::{AuthToken, TokenService, new_auth_token_service},
+use crate::{
+    mailer::{FileMailer, LogMailer, Mailer},
+    service::{
+        AttachmentService, EmailService, ModerationService, PasswordResetService, PostService,
+        RateLimitService, ReportService, UserService,
+        token_service::{
+            AuthToken, RefreshToken, TokenService, new_auth_token_service,
+            new_refresh_token_service,
+        },
+    },
+    storage::{LocalStorage, Storage},
 };
 use std::sync::Arc;
 
@@ -21,10 +31,24 @@ pub struct AppState {
     pub config: Arc<Config>,
     /// Authentication token service
     pub auth_token_service: Arc<TokenService<AuthToken>>,
+    /// Refresh token service
+    pub refresh_token_service: Arc<TokenService<RefreshToken>>,
     /// User service
     pub user_service: Arc<UserService>,
     /// Post service
     pub post_service: Arc<PostService>,
+    /// Email service
+    pub email_service: Arc<EmailService>,
+    /// Password reset service
+    pub password_reset_service: Arc<PasswordResetService>,
+    /// Moderation service
+    pub moderation_service: Arc<ModerationService>,
+    /// Report service
+    pub report_service: Arc<ReportService>,
+    /// Attachment service
+    pub attachment_service: Arc<AttachmentService>,
+    /// Rate limit service
+    pub rate_limit_service: Arc<RateLimitService>,
 }
 
 /// Initializing database connections, builds app state.
@@ -32,10 +56,49 @@ pub async fn bootstrap(config: Config) -> AppState {
     let redis = Redis::new(&config.redis_url).await;
     let db = Database::new(&config.database_url).await;
 
+    let mailer: Arc<dyn Mailer> = match &config.mail_dir {
+        Some(dir) => Arc::new(FileMailer::new(dir)),
+        None => Arc::new(LogMailer),
+    };
+
+    let storage: Arc<dyn Storage> = Arc::new(LocalStorage::new(&config.storage_dir));
+
+    let user_service = Arc::new(UserService::new(db.clone()));
+
+    let attachment_service = Arc::new(AttachmentService::new(db.clone(), storage));
+
+    tokio::spawn(tasks::purge_deleted_accounts(
+        user_service.clone(),
+        config.account_deletion_grace_period,
+    ));
+    tokio::spawn(tasks::collect_orphaned_attachments(
+        attachment_service.clone(),
+        config.orphaned_attachment_grace_period,
+    ));
+
     AppState {
-        auth_token_service: Arc::new(new_auth_token_service(redis, &config.jwt_secret)),
-        user_service: Arc::new(UserService::new(db.clone())),
-        post_service: Arc::new(PostService::new(db)),
+        auth_token_service: Arc::new(new_auth_token_service(redis.clone(), &config.jwt_secret)),
+        refresh_token_service: Arc::new(new_refresh_token_service(
+            redis.clone(),
+            &config.jwt_secret,
+        )),
+        user_service,
+        post_service: Arc::new(PostService::new(db.clone())),
+        email_service: Arc::new(EmailService::new(
+            db.clone(),
+            redis.clone(),
+            mailer.clone(),
+            &config.jwt_secret,
+        )),
+        password_reset_service: Arc::new(PasswordResetService::new(
+            db.clone(),
+            redis.clone(),
+            mailer,
+        )),
+        moderation_service: Arc::new(ModerationService::new(db.clone(), redis.clone())),
+        report_service: Arc::new(ReportService::new(db)),
+        attachment_service,
+        rate_limit_service: Arc::new(RateLimitService::new(redis)),
         config: Arc::new(config),
     }
 }
@@ -0,0 +1,44 @@
+use crate::service::{AttachmentService, UserService};
+use std::{sync::Arc, time::Duration};
+
+/// 1 hour
+static ACCOUNT_PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 1 hour
+static ATTACHMENT_COLLECTION_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Periodically hard-deletes accounts whose deletion grace period is over.
+pub async fn purge_deleted_accounts(user_service: Arc<UserService>, grace_period: u64) {
+    let mut interval = tokio::time::interval(ACCOUNT_PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let purged = user_service.purge_deleted_accounts(grace_period).await;
+
+        if purged > 0 {
+            tracing::info!(purged, "Purged deleted accounts");
+        }
+    }
+}
+
+/// Periodically deletes attachments that are not referenced by any post or
+/// profile once their grace period is over.
+pub async fn collect_orphaned_attachments(
+    attachment_service: Arc<AttachmentService>,
+    grace_period: u64,
+) {
+    let mut interval = tokio::time::interval(ATTACHMENT_COLLECTION_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let deleted = attachment_service
+            .delete_orphaned_attachments(grace_period)
+            .await;
+
+        if deleted > 0 {
+            tracing::info!(deleted, "Deleted orphaned attachments");
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    Config,
-    state::{Database, Redis},
+    AppState, Config,
+    state::{self, Database, Redis},
 };
 
 /// Initializes PostgreSQL connection pool from test environment file.
@@ -14,3 +14,9 @@ pub async fn test_redis() -> Redis {
     let config = Config::from_env(Some(".env.test")).unwrap();
     Redis::new(&config.redis_url).await
 }
+
+/// Builds application state from test environment file.
+pub async fn test_state() -> AppState {
+    let config = Config::from_env(Some(".env.test")).unwrap();
+    state::bootstrap(config).await
+}
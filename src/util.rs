@@ -1,10 +1,24 @@
 use argon2::{
     Argon2, PasswordHash,
-    password_hash::{PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+    password_hash::{
+        PasswordHasher, PasswordVerifier, SaltString,
+        rand_core::{OsRng, RngCore},
+    },
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    time::{SystemTime, UNIX_EPOCH},
 };
-use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::instrument;
 
+/// Boxed future returned from methods of object-safe traits, such as
+/// [`Mailer`] and [`Storage`].
+///
+/// [`Mailer`]: crate::mailer::Mailer
+/// [`Storage`]: crate::storage::Storage
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
 /// Unix timestamp as u64.
 pub fn timestamp() -> u64 {
     SystemTime::now()
@@ -13,6 +27,52 @@ pub fn timestamp() -> u64 {
         .as_secs()
 }
 
+/// Unix timestamp in milliseconds as u64.
+pub fn timestamp_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// Generates a hex-encoded, cryptographically secure random token.
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+
+    hex::encode(bytes)
+}
+
+/// Detects MIME type of a file from its magic bytes.
+///
+/// Only the formats that can be served as attachments are recognized.
+pub fn detect_mime_type(data: &[u8]) -> Option<&'static str> {
+    match data {
+        [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, ..] => Some("image/png"),
+        [0xff, 0xd8, 0xff, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some("image/gif"),
+        [
+            b'R',
+            b'I',
+            b'F',
+            b'F',
+            _,
+            _,
+            _,
+            _,
+            b'W',
+            b'E',
+            b'B',
+            b'P',
+            ..,
+        ] => Some("image/webp"),
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => Some("video/mp4"),
+        [0x1a, 0x45, 0xdf, 0xa3, ..] => Some("video/webm"),
+        [b'%', b'P', b'D', b'F', b'-', ..] => Some("application/pdf"),
+        _ => None,
+    }
+}
+
 /// Runs argon2 password hashing function in blocking thread.
 #[instrument]
 pub async fn argon2_hash(password: String) -> Option<String> {
@@ -48,7 +108,7 @@ pub async fn argon2_verify(password: String, password_hash: String) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use crate::util::{argon2_hash, argon2_verify, timestamp};
+    use crate::util::{argon2_hash, argon2_verify, detect_mime_type, timestamp};
 
     #[tokio::test]
     async fn argon2() {
@@ -58,4 +118,19 @@ mod tests {
 
         assert!(argon2_verify(password, password_hash).await);
     }
+
+    #[test]
+    fn mime_type_detection() {
+        assert_eq!(
+            detect_mime_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            Some("image/png")
+        );
+        assert_eq!(detect_mime_type(b"GIF89a\x01\0"), Some("image/gif"));
+        assert_eq!(
+            detect_mime_type(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some("image/webp")
+        );
+        assert_eq!(detect_mime_type(b"<svg></svg>"), None);
+        assert_eq!(detect_mime_type(b""), None);
+    }
 }